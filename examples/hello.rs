@@ -114,7 +114,7 @@ fn main() -> anyhow::Result<()> {
 
     let mut engine = LayoutEngine::new();
     let size = Size::new(width as f32, height as f32);
-    let layout = engine.compute(&ui, size);
+    let layout = engine.compute(&ui, size)?;
 
     println!("Rendering to framebuffer");
     let renderer = Renderer::new();
@@ -124,7 +124,7 @@ fn main() -> anyhow::Result<()> {
             width,
             height,
         };
-        renderer.render_to(&mut target, &layout, &ui);
+        renderer.render_to(&mut target, &layout, &ui, None)?;
     }
 
     println!("Flushing to display...");
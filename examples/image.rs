@@ -62,7 +62,7 @@ fn main() -> Result<()> {
 
     let mut engine = LayoutEngine::new();
     let size = Size::new(width as f32, height as f32);
-    let layout = engine.compute(&ui, size);
+    let layout = engine.compute(&ui, size)?;
 
     let renderer = Renderer::new();
     {
@@ -71,7 +71,7 @@ fn main() -> Result<()> {
             width,
             height,
         };
-        renderer.render_to(&mut target, &layout, &ui);
+        renderer.render_to(&mut target, &layout, &ui, None)?;
     }
 
     device.flush(it8951::Mode::GLD16)?;
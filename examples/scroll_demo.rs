@@ -43,6 +43,7 @@ fn build_ui(scroll_offset: f32) -> Node {
                 .padding(20.0)
                 .gap(20.0)
                 .scroll_offset(scroll_offset)
+                .scroll_indicator(ScrollIndicator::Bar)
                 .child(Text::new("Item 1: This is the first item in the scroll view").size(32.0))
                 .child(Text::new("Item 2: Second item with some text").size(32.0))
                 .child(Text::new("Item 3: Third item here").size(32.0))
@@ -98,7 +99,7 @@ fn main() -> anyhow::Result<()> {
         device.clear_framebuffer();
 
         let ui = build_ui(offset);
-        let layout = engine.compute(&ui, size);
+        let layout = engine.compute(&ui, size)?;
 
         {
             let mut target = DeviceTarget {
@@ -106,7 +107,7 @@ fn main() -> anyhow::Result<()> {
                 width,
                 height,
             };
-            renderer.render_to(&mut target, &layout, &ui);
+            renderer.render_to(&mut target, &layout, &ui, None)?;
         }
 
         // Use DU mode for fast monochrome updates
@@ -120,14 +121,14 @@ fn main() -> anyhow::Result<()> {
     println!("Final high-quality render...");
     device.clear_framebuffer();
     let ui = build_ui(0.0);
-    let layout = engine.compute(&ui, size);
+    let layout = engine.compute(&ui, size)?;
     {
         let mut target = DeviceTarget {
             device: &mut device,
             width,
             height,
         };
-        renderer.render_to(&mut target, &layout, &ui);
+        renderer.render_to(&mut target, &layout, &ui, None)?;
     }
     device.flush(Mode::GC16)?;
 
@@ -229,7 +229,7 @@ fn main() -> anyhow::Result<()> {
     println!("Computing layout...");
     let mut engine = LayoutEngine::new();
     let size = Size::new(width as f32, height as f32);
-    let layout = engine.compute(&ui, size);
+    let layout = engine.compute(&ui, size)?;
 
     println!("Rendering...");
     let renderer = Renderer::new();
@@ -239,7 +239,7 @@ fn main() -> anyhow::Result<()> {
             width,
             height,
         };
-        renderer.render_to(&mut target, &layout, &ui);
+        renderer.render_to(&mut target, &layout, &ui, None)?;
     }
     device.flush(it8951::Mode::GLD16)?;
 
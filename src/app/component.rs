@@ -0,0 +1,205 @@
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+
+use crate::input::{Action, Gesture};
+use crate::nodes::Node;
+
+/// A reusable piece of UI, such as a toggle or a text input.
+///
+/// The component value holds what its parent passes in each frame, like a
+/// label. What it changes itself lives in `State`, which `Components` keeps
+/// by key between frames. Events come back to the parent as `Message`s.
+pub trait Component {
+    type State: Default + 'static;
+    type Message;
+
+    fn view(&self, state: &Self::State) -> Node;
+
+    /// Handle an action aimed at the component, such as `Select` while it
+    /// is focused
+    fn on_action(&self, _state: &mut Self::State, _action: Action) -> Option<Self::Message> {
+        None
+    }
+
+    fn on_gesture(&self, _state: &mut Self::State, _gesture: Gesture) -> Option<Self::Message> {
+        None
+    }
+}
+
+/// Local state of components, by key and component type.
+///
+/// Keep one in the app and render components through `view`. Giving the
+/// component's root node the same key lets gestures find it again through
+/// `HitTestResult::key`.
+///
+/// State stays until it is removed, even while its component isn't shown,
+/// so a hidden toggle keeps its value. Drop the state of components that
+/// are gone for good with `remove` or `retain`, e.g. the items of a list
+/// after they are deleted.
+#[derive(Debug, Default)]
+pub struct Components {
+    states: HashMap<String, (TypeId, Box<dyn Any>)>,
+}
+
+impl Components {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Render a component with its state, or a default state if it has
+    /// none yet
+    pub fn view<C: Component + 'static>(&self, key: &str, component: &C) -> Node {
+        match self.state::<C>(key) {
+            Some(state) => component.view(state),
+            None => component.view(&C::State::default()),
+        }
+    }
+
+    /// State of the `C` component with the given key, if it has any
+    pub fn state<C: Component + 'static>(&self, key: &str) -> Option<&C::State> {
+        match self.states.get(key)? {
+            (component, state) if *component == TypeId::of::<C>() => state.downcast_ref(),
+            _ => None,
+        }
+    }
+
+    /// State of the `C` component with the given key; a default state
+    /// replaces a missing one, or one of another component type
+    pub fn state_mut<C: Component + 'static>(&mut self, key: &str) -> &mut C::State {
+        let (component, state) = self
+            .states
+            .entry(key.to_string())
+            .or_insert_with(|| (TypeId::of::<C>(), Box::new(C::State::default())));
+        if *component != TypeId::of::<C>() {
+            *component = TypeId::of::<C>();
+            *state = Box::new(C::State::default());
+        }
+        state
+            .downcast_mut()
+            .expect("state was just replaced with the component's type")
+    }
+
+    pub fn on_action<C: Component + 'static>(
+        &mut self,
+        key: &str,
+        component: &C,
+        action: Action,
+    ) -> Option<C::Message> {
+        component.on_action(self.state_mut::<C>(key), action)
+    }
+
+    pub fn on_gesture<C: Component + 'static>(
+        &mut self,
+        key: &str,
+        component: &C,
+        gesture: Gesture,
+    ) -> Option<C::Message> {
+        component.on_gesture(self.state_mut::<C>(key), gesture)
+    }
+
+    /// Drop the state of a component, e.g. one that is no longer shown
+    pub fn remove(&mut self, key: &str) -> bool {
+        self.states.remove(key).is_some()
+    }
+
+    /// Keep only the states whose key passes `keep`
+    pub fn retain(&mut self, mut keep: impl FnMut(&str) -> bool) {
+        self.states.retain(|key, _| keep(key));
+    }
+
+    pub fn len(&self) -> usize {
+        self.states.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.states.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nodes::Text;
+
+    struct Toggle {
+        label: &'static str,
+    }
+
+    impl Component for Toggle {
+        type State = bool;
+        type Message = bool;
+
+        fn view(&self, on: &bool) -> Node {
+            let state = if *on { "on" } else { "off" };
+            Text::new(format!("{}: {}", self.label, state)).build()
+        }
+
+        fn on_action(&self, on: &mut bool, action: Action) -> Option<bool> {
+            if action != Action::Select {
+                return None;
+            }
+            *on = !*on;
+            Some(*on)
+        }
+    }
+
+    fn content(node: &Node) -> &str {
+        match node {
+            Node::Text(text) => &text.content,
+            _ => panic!("expected a text node"),
+        }
+    }
+
+    #[test]
+    fn test_state_persists_by_key() {
+        let mut components = Components::new();
+        let wifi = Toggle { label: "Wi-Fi" };
+        assert_eq!(content(&components.view("wifi", &wifi)), "Wi-Fi: off");
+        assert!(components.is_empty());
+
+        assert_eq!(
+            components.on_action("wifi", &wifi, Action::Select),
+            Some(true)
+        );
+        assert_eq!(components.on_action("wifi", &wifi, Action::Back), None);
+
+        // a new instance with the same key picks the state up again
+        let relabeled = Toggle { label: "Wireless" };
+        assert_eq!(
+            content(&components.view("wifi", &relabeled)),
+            "Wireless: on"
+        );
+        assert_eq!(
+            content(&components.view("bluetooth", &relabeled)),
+            "Wireless: off"
+        );
+
+        assert!(components.remove("wifi"));
+        assert_eq!(components.state::<Toggle>("wifi"), None);
+    }
+
+    /// Another component with a `bool` state
+    struct Checkbox;
+
+    impl Component for Checkbox {
+        type State = bool;
+        type Message = ();
+
+        fn view(&self, checked: &bool) -> Node {
+            Text::new(if *checked { "[x]" } else { "[ ]" }).build()
+        }
+    }
+
+    #[test]
+    fn test_state_is_per_component_type() {
+        let mut components = Components::new();
+        *components.state_mut::<Toggle>("option") = true;
+
+        assert_eq!(components.state::<Checkbox>("option"), None);
+        assert_eq!(content(&components.view("option", &Checkbox)), "[ ]");
+
+        // the checkbox takes the key over with a fresh state
+        assert!(!*components.state_mut::<Checkbox>("option"));
+        assert_eq!(components.state::<Toggle>("option"), None);
+    }
+}
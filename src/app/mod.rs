@@ -0,0 +1,9 @@
+mod component;
+mod navigator;
+mod runtime;
+mod scheduler;
+
+pub use component::{Component, Components};
+pub use navigator::{Navigation, Navigator, ScreenState};
+pub use runtime::{App, Runtime};
+pub use scheduler::{Scheduler, TimerId};
@@ -0,0 +1,225 @@
+use std::collections::HashMap;
+
+use crate::focus::FocusState;
+use crate::scroll::ScrollController;
+
+/// What a `Runtime` needs from an app that navigates between screens, see
+/// `App::navigation`
+pub trait Navigation {
+    /// Focus of the current screen
+    fn focus(&self) -> &FocusState;
+
+    fn focus_mut(&mut self) -> &mut FocusState;
+
+    /// Leave the current screen for the one below it; false on the root
+    /// screen
+    fn back(&mut self) -> bool;
+
+    /// Whether the screen changed since the last call
+    fn take_transition(&mut self) -> bool;
+
+    /// Whether a screen change should be flushed with a full refresh
+    fn full_refresh(&self) -> bool;
+}
+
+/// State a screen keeps while other screens are on top of it
+#[derive(Debug, Clone, Default)]
+pub struct ScreenState {
+    pub focus: FocusState,
+    scroll: HashMap<String, ScrollController>,
+}
+
+impl ScreenState {
+    /// Scroll position of the container with the given key
+    pub fn scroll(&self, key: &str) -> ScrollController {
+        self.scroll.get(key).copied().unwrap_or_default()
+    }
+
+    pub fn scroll_mut(&mut self, key: &str) -> &mut ScrollController {
+        self.scroll.entry(key.to_string()).or_default()
+    }
+}
+
+/// A stack of screens, such as library, book and settings, each keeping its
+/// own focus and scroll positions while covered.
+///
+/// `S` identifies a screen, usually an app enum. Hand the navigator to the
+/// `Runtime` through `App::navigation` and `App::navigation_mut` and it
+/// drives the current screen's focus, goes back on the `Back` action and
+/// redraws on screen changes.
+#[derive(Debug, Clone)]
+pub struct Navigator<S> {
+    stack: Vec<(S, ScreenState)>,
+    /// Focus settings, such as wrapping, for new screens
+    focus: FocusState,
+    full_refresh: bool,
+    transitioned: bool,
+}
+
+impl<S> Navigator<S> {
+    pub fn new(root: S) -> Self {
+        Self {
+            stack: vec![(root, ScreenState::default())],
+            focus: FocusState::new(),
+            full_refresh: false,
+            transitioned: false,
+        }
+    }
+
+    /// Focus settings for the root screen and screens pushed from now on
+    pub fn focus(mut self, focus: FocusState) -> Self {
+        for (_, state) in &mut self.stack {
+            state.focus = focus.clone();
+        }
+        self.focus = focus;
+        self
+    }
+
+    /// Flush screen changes with a full refresh, clearing e-ink ghosting
+    pub fn full_refresh(mut self, full_refresh: bool) -> Self {
+        self.full_refresh = full_refresh;
+        self
+    }
+
+    pub fn current(&self) -> &S {
+        &self.top().0
+    }
+
+    pub fn current_mut(&mut self) -> &mut S {
+        &mut self.top_mut().0
+    }
+
+    /// Focus and scroll state of the current screen
+    pub fn state(&self) -> &ScreenState {
+        &self.top().1
+    }
+
+    pub fn state_mut(&mut self) -> &mut ScreenState {
+        &mut self.top_mut().1
+    }
+
+    /// Number of screens on the stack, 1 on the root screen
+    pub fn depth(&self) -> usize {
+        self.stack.len()
+    }
+
+    /// Screens from the root up to the current one
+    pub fn screens(&self) -> impl Iterator<Item = &S> {
+        self.stack.iter().map(|(screen, _)| screen)
+    }
+
+    pub fn push(&mut self, screen: S) {
+        let state = ScreenState {
+            focus: self.focus.clone(),
+            ..Default::default()
+        };
+        self.stack.push((screen, state));
+        self.transitioned = true;
+    }
+
+    /// Remove the current screen and return to the one below, with its
+    /// focus and scroll positions as they were. The root screen stays.
+    pub fn pop(&mut self) -> Option<S> {
+        if self.stack.len() < 2 {
+            return None;
+        }
+        self.transitioned = true;
+        self.stack.pop().map(|(screen, _)| screen)
+    }
+
+    /// Swap the current screen for a new one with fresh state
+    pub fn replace(&mut self, screen: S) -> S {
+        let state = ScreenState {
+            focus: self.focus.clone(),
+            ..Default::default()
+        };
+        self.transitioned = true;
+        std::mem::replace(self.top_mut(), (screen, state)).0
+    }
+
+    fn top(&self) -> &(S, ScreenState) {
+        self.stack
+            .last()
+            .expect("navigator always has a root screen")
+    }
+
+    fn top_mut(&mut self) -> &mut (S, ScreenState) {
+        self.stack
+            .last_mut()
+            .expect("navigator always has a root screen")
+    }
+}
+
+impl<S> Navigation for Navigator<S> {
+    fn focus(&self) -> &FocusState {
+        &self.state().focus
+    }
+
+    fn focus_mut(&mut self) -> &mut FocusState {
+        &mut self.state_mut().focus
+    }
+
+    fn back(&mut self) -> bool {
+        self.pop().is_some()
+    }
+
+    fn take_transition(&mut self) -> bool {
+        std::mem::take(&mut self.transitioned)
+    }
+
+    fn full_refresh(&self) -> bool {
+        self.full_refresh
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::style::Rect;
+
+    #[derive(Debug, PartialEq)]
+    enum Screen {
+        Library,
+        Book(u32),
+        Settings,
+    }
+
+    #[test]
+    fn test_screens_keep_their_state() {
+        let mut nav = Navigator::new(Screen::Library);
+        nav.state_mut().focus.set_focus(3);
+        nav.state_mut().scroll_mut("shelf").offset_y = 120.0;
+
+        nav.push(Screen::Book(7));
+        assert_eq!(nav.current(), &Screen::Book(7));
+        assert_eq!(nav.state().focus.focused_id, None);
+        assert_eq!(nav.state().scroll("shelf").offset_y, 0.0);
+
+        assert_eq!(nav.replace(Screen::Settings), Screen::Book(7));
+        assert_eq!(nav.depth(), 2);
+
+        assert_eq!(nav.pop(), Some(Screen::Settings));
+        assert_eq!(nav.state().focus.focused_id, Some(3));
+        assert_eq!(nav.state().scroll("shelf").offset_y, 120.0);
+        assert!(nav.take_transition());
+        assert!(!nav.take_transition());
+
+        // the root screen can't be popped
+        assert_eq!(nav.pop(), None);
+        assert!(!nav.back());
+    }
+
+    #[test]
+    fn test_focus_settings_apply_to_the_root_screen() {
+        let mut nav = Navigator::new(Screen::Library).focus(FocusState::new().wrap(true));
+        for id in [1, 2] {
+            nav.state_mut()
+                .focus
+                .register(id, Rect::new(0.0, id as f32 * 20.0, 100.0, 20.0));
+        }
+        nav.state_mut().focus.set_focus(2);
+
+        nav.state_mut().focus.focus_next();
+        assert_eq!(nav.state().focus.focused_id, Some(1));
+    }
+}
@@ -0,0 +1,651 @@
+use std::time::{Duration, Instant};
+
+use crate::error::{Error, Result};
+use crate::focus::{FocusId, FocusState};
+use crate::input::{Action, Gesture, GestureRecognizer, InputEvent, InputSource, Keymap};
+use crate::layout::{HitTestResult, LayoutEngine, LayoutTree};
+use crate::nodes::Node;
+use crate::render::{DeviceAPI, DisplayMode, RenderTarget, Renderer};
+use crate::style::Size;
+
+use super::navigator::Navigation;
+use super::scheduler::Scheduler;
+
+/// An application driven by a `Runtime`.
+///
+/// The runtime calls `view` to draw the current state, and turns input into
+/// messages through `on_action` and `on_gesture`, which it passes to
+/// `update` along with the messages of timers.
+pub trait App {
+    type Message;
+
+    /// Called once when the runtime is created, to start timers
+    fn init(&mut self, _scheduler: &mut Scheduler<Self::Message>) {}
+
+    /// Apply a message; returns whether the view changed and needs a redraw.
+    /// `scheduler` starts and cancels timers in response, e.g. to hide a
+    /// notice after a while.
+    fn update(&mut self, msg: Self::Message, scheduler: &mut Scheduler<Self::Message>) -> bool;
+
+    fn view(&self) -> Node;
+
+    /// Message for an action the keymap didn't use to move the focus.
+    /// `focused` is the focused node, e.g. the target of `Select`.
+    fn on_action(&self, _action: Action, _focused: Option<FocusId>) -> Option<Self::Message> {
+        None
+    }
+
+    /// Message for a gesture aimed at `target`, the node under it
+    fn on_gesture(
+        &self,
+        _gesture: Gesture,
+        _target: Option<HitTestResult<'_>>,
+    ) -> Option<Self::Message> {
+        None
+    }
+
+    /// The app's `Navigator`, if it has screens. The runtime then moves the
+    /// focus of the current screen and handles `Back` by popping it.
+    fn navigation(&self) -> Option<&dyn Navigation> {
+        None
+    }
+
+    /// Same as `navigation`, and must return the same navigator
+    fn navigation_mut(&mut self) -> Option<&mut dyn Navigation> {
+        None
+    }
+
+    /// `Runtime::run` returns once this is true
+    fn should_exit(&self) -> bool {
+        false
+    }
+}
+
+/// Owns an `App` and everything needed to show it: the layout engine,
+/// renderer, focus, input and display.
+///
+/// The view is rebuilt, laid out and flushed only after an update reports
+/// a change or the focus moves.
+pub struct Runtime<A: App, D: DeviceAPI, I: InputSource> {
+    app: A,
+    device: D,
+    input: I,
+    engine: LayoutEngine,
+    renderer: Renderer,
+    focus: FocusState,
+    keymap: Keymap,
+    gestures: GestureRecognizer,
+    display_mode: DisplayMode,
+    transition_mode: DisplayMode,
+    /// Flush the next frame with `transition_mode`, as for the first one
+    full_refresh: bool,
+    scheduler: Scheduler<A::Message>,
+    /// Layout of the last frame, for routing gestures
+    layout: Option<LayoutTree>,
+    dirty: bool,
+}
+
+impl<A: App, D: DeviceAPI, I: InputSource> Runtime<A, D, I> {
+    pub fn new(mut app: A, device: D, input: I) -> Self {
+        let mut scheduler = Scheduler::new();
+        app.init(&mut scheduler);
+
+        Self {
+            app,
+            device,
+            input,
+            engine: LayoutEngine::new(),
+            renderer: Renderer::new(),
+            focus: FocusState::new(),
+            keymap: Keymap::standard(),
+            gestures: GestureRecognizer::new(),
+            display_mode: DisplayMode::GC16,
+            transition_mode: DisplayMode::GC16,
+            full_refresh: true,
+            scheduler,
+            layout: None,
+            dirty: true,
+        }
+    }
+
+    pub fn renderer(mut self, renderer: Renderer) -> Self {
+        self.renderer = renderer;
+        self
+    }
+
+    /// Focus settings such as wrapping, for apps without a navigator;
+    /// focusables come from the layout
+    pub fn focus(mut self, focus: FocusState) -> Self {
+        self.focus = focus;
+        self
+    }
+
+    pub fn keymap(mut self, keymap: Keymap) -> Self {
+        self.keymap = keymap;
+        self
+    }
+
+    pub fn gestures(mut self, gestures: GestureRecognizer) -> Self {
+        self.gestures = gestures;
+        self
+    }
+
+    /// Waveform used to flush each frame after the first
+    pub fn display_mode(mut self, mode: DisplayMode) -> Self {
+        self.display_mode = mode;
+        self
+    }
+
+    /// Waveform for the very first frame, and for the first frame of a new
+    /// screen when the navigator asks for a full refresh
+    pub fn transition_mode(mut self, mode: DisplayMode) -> Self {
+        self.transition_mode = mode;
+        self
+    }
+
+    pub fn app(&self) -> &A {
+        &self.app
+    }
+
+    pub fn device(&self) -> &D {
+        &self.device
+    }
+
+    pub fn device_mut(&mut self) -> &mut D {
+        &mut self.device
+    }
+
+    /// Focus of the current screen, or the runtime's own without a
+    /// navigator
+    pub fn focus_state(&self) -> &FocusState {
+        match self.app.navigation() {
+            Some(nav) => nav.focus(),
+            None => &self.focus,
+        }
+    }
+
+    pub fn focus_state_mut(&mut self) -> &mut FocusState {
+        self.dirty = true;
+        self.focus_mut()
+    }
+
+    fn focus_mut(&mut self) -> &mut FocusState {
+        match self.app.navigation_mut() {
+            Some(nav) => nav.focus_mut(),
+            None => &mut self.focus,
+        }
+    }
+
+    /// Timers that deliver messages to the app
+    pub fn scheduler_mut(&mut self) -> &mut Scheduler<A::Message> {
+        &mut self.scheduler
+    }
+
+    /// When a timer or a pending gesture next needs the runtime, if ever.
+    /// Until then it only has to wake up for input.
+    pub fn next_wakeup(&self) -> Option<Instant> {
+        match (self.scheduler.next_wakeup(), self.gestures.next_deadline()) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        }
+    }
+
+    /// Layout of the last rendered frame
+    pub fn layout(&self) -> Option<&LayoutTree> {
+        self.layout.as_ref()
+    }
+
+    /// Force a redraw on the next `render`
+    pub fn invalidate(&mut self) {
+        self.dirty = true;
+    }
+
+    pub fn send(&mut self, msg: A::Message) {
+        if self.app.update(msg, &mut self.scheduler) {
+            self.dirty = true;
+        }
+        self.check_transition();
+    }
+
+    /// Route an input event through the gesture recognizer and the keymap.
+    /// `Back` pops the navigator's screen, unless it is on its root.
+    pub fn handle_event(&mut self, event: &InputEvent) {
+        let now = Instant::now();
+        if let Some(gesture) = self.gestures.handle(event, now) {
+            self.handle_gesture(gesture);
+        }
+        // gestures recognized behind it, such as a swipe after a held tap
+        while let Some(gesture) = self.gestures.update(now) {
+            self.handle_gesture(gesture);
+        }
+
+        let focus = match self.app.navigation_mut() {
+            Some(nav) => nav.focus_mut(),
+            None => &mut self.focus,
+        };
+        let previous = focus.focused_id;
+        let action = self.keymap.dispatch(event, focus);
+        let focused = focus.focused_id;
+        if focused != previous {
+            self.dirty = true;
+        }
+
+        match action {
+            Some(Action::Back) if self.app.navigation_mut().is_some_and(|nav| nav.back()) => {
+                self.check_transition();
+            }
+            Some(action) => {
+                if let Some(msg) = self.app.on_action(action, focused) {
+                    self.send(msg);
+                }
+            }
+            None => {}
+        }
+    }
+
+    /// Redraw after the navigator changed screens
+    fn check_transition(&mut self) {
+        if let Some(nav) = self.app.navigation_mut() {
+            if nav.take_transition() {
+                self.dirty = true;
+                self.full_refresh |= nav.full_refresh();
+            }
+        }
+    }
+
+    fn handle_gesture(&mut self, gesture: Gesture) {
+        let target = self
+            .layout
+            .as_ref()
+            .and_then(|layout| gesture.target(layout));
+        if let Some(msg) = self.app.on_gesture(gesture, target) {
+            self.send(msg);
+        }
+    }
+
+    /// Redraw and flush if anything changed since the last frame
+    pub fn render(&mut self) -> Result<()> {
+        if !self.dirty {
+            return Ok(());
+        }
+
+        let root = self.app.view();
+        let (width, height) = self.device.dimensions();
+        let layout = self
+            .engine
+            .compute(&root, Size::new(width as f32, height as f32))?;
+        let focus = match self.app.navigation_mut() {
+            Some(nav) => nav.focus_mut(),
+            None => &mut self.focus,
+        };
+        focus.update_from_layout(&layout);
+
+        self.device.clear_framebuffer();
+        let mut target = DeviceTarget {
+            device: &mut self.device,
+        };
+        self.renderer
+            .render_to(&mut target, &layout, &root, Some(focus))?;
+
+        let mode = if self.full_refresh {
+            self.transition_mode
+        } else {
+            self.display_mode
+        };
+        self.device.flush(mode).map_err(Error::Device)?;
+
+        // a frame that failed is drawn again by the next call
+        self.dirty = false;
+        self.full_refresh = false;
+        self.layout = Some(layout);
+        Ok(())
+    }
+
+    /// Render if needed, then wait for one input event and handle it, along
+    /// with the timers and gestures that came due meanwhile.
+    ///
+    /// The wait ends at the next wakeup or after `timeout`, whichever is
+    /// sooner; with neither it blocks until there is input.
+    pub fn step(&mut self, timeout: Option<Duration>) -> Result<()> {
+        self.render()?;
+
+        let now = Instant::now();
+        let wakeup = self
+            .next_wakeup()
+            .map(|wakeup| wakeup.saturating_duration_since(now));
+        let timeout = match (timeout, wakeup) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+
+        if let Some(event) = self.input.wait_event(timeout)? {
+            self.handle_event(&event);
+        }
+
+        let now = Instant::now();
+        for msg in self.scheduler.due(now) {
+            self.send(msg);
+        }
+        while let Some(gesture) = self.gestures.update(now) {
+            self.handle_gesture(gesture);
+        }
+
+        self.render()
+    }
+
+    /// Step until the app wants to exit
+    pub fn run(&mut self) -> Result<()> {
+        while !self.app.should_exit() {
+            self.step(None)?;
+        }
+        Ok(())
+    }
+}
+
+struct DeviceTarget<'a, D: DeviceAPI> {
+    device: &'a mut D,
+}
+
+impl<D: DeviceAPI> RenderTarget for DeviceTarget<'_, D> {
+    fn width(&self) -> u32 {
+        self.device.dimensions().0
+    }
+
+    fn height(&self) -> u32 {
+        self.device.dimensions().1
+    }
+
+    fn set_pixel(&mut self, x: i32, y: i32, color: u8) {
+        self.device.set_pixel(x, y, color);
+    }
+
+    fn get_pixel(&self, x: i32, y: i32) -> u8 {
+        self.device.get_pixel(x, y)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use image::{GrayImage, Luma};
+
+    use super::*;
+    use crate::app::Navigator;
+    use crate::input::{ScriptedInput, KEY_BACK, KEY_DOWN, KEY_ENTER};
+    use crate::nodes::{Text, View};
+    use crate::render::Rotation;
+    use crate::style::Dimension;
+
+    /// An in-memory display that counts flushes
+    struct MockDevice {
+        image: GrayImage,
+        flushes: usize,
+        modes: Vec<DisplayMode>,
+        /// Number of upcoming flushes that fail
+        failures: usize,
+    }
+
+    impl MockDevice {
+        fn new() -> Self {
+            Self {
+                image: GrayImage::from_pixel(200, 200, Luma([255])),
+                flushes: 0,
+                modes: Vec::new(),
+                failures: 0,
+            }
+        }
+    }
+
+    impl DeviceAPI for MockDevice {
+        fn set_pixel(&mut self, x: i32, y: i32, color: u8) {
+            RenderTarget::set_pixel(&mut self.image, x, y, color);
+        }
+
+        fn get_pixel(&self, x: i32, y: i32) -> u8 {
+            RenderTarget::get_pixel(&self.image, x, y)
+        }
+
+        fn dimensions(&self) -> (u32, u32) {
+            self.image.dimensions()
+        }
+
+        fn flush(&mut self, mode: DisplayMode) -> std::result::Result<(), anyhow::Error> {
+            if self.failures > 0 {
+                self.failures -= 1;
+                anyhow::bail!("panel busy");
+            }
+            self.flushes += 1;
+            self.modes.push(mode);
+            Ok(())
+        }
+
+        fn clear_framebuffer(&mut self) {
+            self.image = GrayImage::from_pixel(200, 200, Luma([255]));
+        }
+
+        fn set_rotation(&mut self, _rotation: Rotation) {}
+
+        fn rotation(&self) -> Rotation {
+            Rotation::Rotate0
+        }
+    }
+
+    /// Two buttons; selecting one counts a press
+    struct Counter {
+        presses: u32,
+    }
+
+    impl App for Counter {
+        type Message = FocusId;
+
+        fn update(&mut self, _button: FocusId, _scheduler: &mut Scheduler<FocusId>) -> bool {
+            self.presses += 1;
+            true
+        }
+
+        fn view(&self) -> Node {
+            View::column()
+                .width(Dimension::Percent(1.0))
+                .child(Text::new(format!("{}", self.presses)).focusable(1))
+                .child(Text::new("+").focusable(2))
+                .build()
+        }
+
+        fn on_action(&self, action: Action, focused: Option<FocusId>) -> Option<FocusId> {
+            match action {
+                Action::Select => focused,
+                _ => None,
+            }
+        }
+    }
+
+    #[test]
+    fn test_runtime_renders_on_change() {
+        let input = ScriptedInput::new([
+            InputEvent::key_press(KEY_DOWN),
+            InputEvent::key_release(KEY_DOWN),
+            InputEvent::key_press(KEY_ENTER),
+        ]);
+        let mut runtime = Runtime::new(Counter { presses: 0 }, MockDevice::new(), input);
+
+        runtime.render().unwrap();
+        runtime.render().unwrap();
+        assert_eq!(runtime.device().flushes, 1);
+
+        // the first press focuses the first button, which redraws
+        runtime.step(None).unwrap();
+        assert_eq!(runtime.focus_state().focused_id, Some(1));
+        assert_eq!(runtime.device().flushes, 2);
+
+        // releases map to no action
+        runtime.step(None).unwrap();
+        assert_eq!(runtime.device().flushes, 2);
+
+        runtime.step(None).unwrap();
+        assert_eq!(runtime.app().presses, 1);
+        assert_eq!(runtime.device().flushes, 3);
+    }
+
+    #[test]
+    fn test_runtime_retries_failed_frame() {
+        let mut device = MockDevice::new();
+        device.failures = 1;
+        let mut runtime = Runtime::new(Counter { presses: 0 }, device, ScriptedInput::default());
+
+        assert!(matches!(runtime.render(), Err(Error::Device(_))));
+        assert_eq!(runtime.device().flushes, 0);
+        runtime.render().unwrap();
+        assert_eq!(runtime.device().flushes, 1);
+    }
+
+    /// Redraws on a timer, like a dashboard clock
+    struct Clock {
+        ticks: u32,
+    }
+
+    impl App for Clock {
+        type Message = ();
+
+        fn init(&mut self, scheduler: &mut Scheduler<()>) {
+            scheduler.every_from(Instant::now(), Duration::from_secs(60), ());
+        }
+
+        fn update(&mut self, _msg: (), _scheduler: &mut Scheduler<()>) -> bool {
+            self.ticks += 1;
+            true
+        }
+
+        fn view(&self) -> Node {
+            Text::new(format!("{}", self.ticks)).build()
+        }
+    }
+
+    #[test]
+    fn test_runtime_delivers_timers() {
+        let mut runtime = Runtime::new(
+            Clock { ticks: 0 },
+            MockDevice::new(),
+            ScriptedInput::default(),
+        );
+
+        runtime.step(None).unwrap();
+        assert_eq!(runtime.app().ticks, 1);
+        assert_eq!(runtime.device().flushes, 2);
+
+        // the next tick is a minute away
+        let wakeup = runtime.next_wakeup().unwrap();
+        assert!(wakeup > Instant::now() + Duration::from_secs(59));
+    }
+
+    /// Shows a notice on `Select` and hides it after a few seconds
+    struct Notice {
+        shown: bool,
+    }
+
+    impl App for Notice {
+        type Message = bool;
+
+        fn update(&mut self, show: bool, scheduler: &mut Scheduler<bool>) -> bool {
+            if show {
+                scheduler.after(Duration::from_secs(3), false);
+            }
+            self.shown = show;
+            true
+        }
+
+        fn view(&self) -> Node {
+            Text::new(if self.shown { "Saved" } else { "" }).build()
+        }
+
+        fn on_action(&self, action: Action, _focused: Option<FocusId>) -> Option<bool> {
+            (action == Action::Select).then_some(true)
+        }
+    }
+
+    #[test]
+    fn test_update_starts_timers() {
+        let input = ScriptedInput::new([InputEvent::key_press(KEY_ENTER)]);
+        let mut runtime = Runtime::new(Notice { shown: false }, MockDevice::new(), input);
+        assert_eq!(runtime.next_wakeup(), None);
+
+        runtime.step(None).unwrap();
+        assert!(runtime.app().shown);
+        let wakeup = runtime.next_wakeup().unwrap();
+        assert!(wakeup > Instant::now() + Duration::from_secs(2));
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    enum Screen {
+        Library,
+        Book,
+    }
+
+    struct Reader {
+        nav: Navigator<Screen>,
+    }
+
+    impl App for Reader {
+        type Message = ();
+
+        fn update(&mut self, _open: (), _scheduler: &mut Scheduler<()>) -> bool {
+            self.nav.push(Screen::Book);
+            false
+        }
+
+        fn view(&self) -> Node {
+            let books = match self.nav.current() {
+                Screen::Library => 2,
+                Screen::Book => 0,
+            };
+            View::column()
+                .width(Dimension::Percent(1.0))
+                .children((0..books).map(|id| Text::new("Book").focusable(id)))
+                .build()
+        }
+
+        fn on_action(&self, action: Action, _focused: Option<FocusId>) -> Option<()> {
+            (action == Action::Select).then_some(())
+        }
+
+        fn navigation(&self) -> Option<&dyn Navigation> {
+            Some(&self.nav)
+        }
+
+        fn navigation_mut(&mut self) -> Option<&mut dyn Navigation> {
+            Some(&mut self.nav)
+        }
+    }
+
+    #[test]
+    fn test_runtime_navigation() {
+        let input = ScriptedInput::new([
+            InputEvent::key_press(KEY_DOWN),
+            InputEvent::key_press(KEY_DOWN),
+            InputEvent::key_press(KEY_ENTER),
+            InputEvent::key_press(KEY_BACK),
+            InputEvent::key_press(KEY_BACK),
+        ]);
+        let app = Reader {
+            nav: Navigator::new(Screen::Library).full_refresh(true),
+        };
+        let mut runtime =
+            Runtime::new(app, MockDevice::new(), input).display_mode(DisplayMode::GL16);
+
+        runtime.step(None).unwrap();
+        runtime.step(None).unwrap();
+        assert_eq!(runtime.focus_state().focused_id, Some(1));
+
+        runtime.step(None).unwrap();
+        assert_eq!(runtime.app().nav.current(), &Screen::Book);
+        assert_eq!(runtime.focus_state().focused_id, None);
+
+        // back to the library, focus where it was
+        runtime.step(None).unwrap();
+        assert_eq!(runtime.app().nav.current(), &Screen::Library);
+        assert_eq!(runtime.focus_state().focused_id, Some(1));
+
+        // back on the root screen goes to the app, which ignores it
+        runtime.step(None).unwrap();
+        use DisplayMode::*;
+        assert_eq!(runtime.device().modes, [GC16, GL16, GL16, GC16, GC16]);
+    }
+}
@@ -0,0 +1,178 @@
+use std::time::{Duration, Instant};
+
+pub type TimerId = u64;
+
+struct Timer<M> {
+    id: TimerId,
+    due: Instant,
+    kind: TimerKind<M>,
+}
+
+enum TimerKind<M> {
+    Once(M),
+    Every {
+        period: Duration,
+        message: Box<dyn FnMut() -> M>,
+    },
+}
+
+/// One-shot and periodic timers that deliver messages.
+///
+/// `next_wakeup` tells how long the process can sleep, so a dashboard can
+/// block, or suspend the panel controller, until the next timer is due.
+pub struct Scheduler<M> {
+    timers: Vec<Timer<M>>,
+    next_id: TimerId,
+}
+
+impl<M> Default for Scheduler<M> {
+    fn default() -> Self {
+        Self {
+            timers: Vec::new(),
+            next_id: 0,
+        }
+    }
+}
+
+impl<M> Scheduler<M> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Deliver `message` once, `delay` from now
+    pub fn after(&mut self, delay: Duration, message: M) -> TimerId {
+        self.at(Instant::now() + delay, message)
+    }
+
+    /// Deliver `message` once at `when`
+    pub fn at(&mut self, when: Instant, message: M) -> TimerId {
+        self.add(when, TimerKind::Once(message))
+    }
+
+    /// Deliver `message` every `period`, starting one period from now
+    ///
+    /// # Panics
+    ///
+    /// If `period` is zero.
+    pub fn every(&mut self, period: Duration, message: M) -> TimerId
+    where
+        M: Clone + 'static,
+    {
+        self.every_from(Instant::now() + period, period, message)
+    }
+
+    /// Deliver `message` at `first` and then every `period`, e.g. starting
+    /// on the next full minute for a clock
+    ///
+    /// # Panics
+    ///
+    /// If `period` is zero.
+    pub fn every_from(&mut self, first: Instant, period: Duration, message: M) -> TimerId
+    where
+        M: Clone + 'static,
+    {
+        assert!(!period.is_zero(), "timer period must be non-zero");
+        self.add(
+            first,
+            TimerKind::Every {
+                period,
+                message: Box::new(move || message.clone()),
+            },
+        )
+    }
+
+    /// Stop a timer; returns false if it already fired or was cancelled
+    pub fn cancel(&mut self, id: TimerId) -> bool {
+        let count = self.timers.len();
+        self.timers.retain(|timer| timer.id != id);
+        self.timers.len() != count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.timers.is_empty()
+    }
+
+    /// When the earliest timer is due
+    pub fn next_wakeup(&self) -> Option<Instant> {
+        self.timers.iter().map(|timer| timer.due).min()
+    }
+
+    /// Messages of the timers due at `now`, earliest first. A periodic
+    /// timer that missed several periods fires once and is moved to its
+    /// next period after `now`.
+    pub fn due(&mut self, now: Instant) -> Vec<M> {
+        let mut fired: Vec<(Instant, M)> = Vec::new();
+        for mut timer in std::mem::take(&mut self.timers) {
+            if timer.due > now {
+                self.timers.push(timer);
+                continue;
+            }
+            match timer.kind {
+                TimerKind::Once(message) => fired.push((timer.due, message)),
+                TimerKind::Every {
+                    period,
+                    ref mut message,
+                } => {
+                    fired.push((timer.due, message()));
+                    while timer.due <= now {
+                        timer.due += period;
+                    }
+                    self.timers.push(timer);
+                }
+            }
+        }
+
+        fired.sort_by_key(|(due, _)| *due);
+        fired.into_iter().map(|(_, message)| message).collect()
+    }
+
+    fn add(&mut self, due: Instant, kind: TimerKind<M>) -> TimerId {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.timers.push(Timer { id, due, kind });
+        id
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn secs(s: u64) -> Duration {
+        Duration::from_secs(s)
+    }
+
+    #[test]
+    fn test_one_shot_and_periodic() {
+        let start = Instant::now();
+        let mut scheduler = Scheduler::new();
+        scheduler.every_from(start + secs(60), secs(60), "clock");
+        scheduler.at(start + secs(90), "weather");
+        assert_eq!(scheduler.next_wakeup(), Some(start + secs(60)));
+
+        assert!(scheduler.due(start + secs(59)).is_empty());
+        assert_eq!(scheduler.due(start + secs(60)), ["clock"]);
+        assert_eq!(scheduler.next_wakeup(), Some(start + secs(90)));
+
+        // missed periods fire once
+        assert_eq!(scheduler.due(start + secs(250)), ["weather", "clock"]);
+        assert_eq!(scheduler.next_wakeup(), Some(start + secs(300)));
+    }
+
+    #[test]
+    fn test_cancel() {
+        let start = Instant::now();
+        let mut scheduler = Scheduler::new();
+        let id = scheduler.every_from(start, secs(1), 1);
+        assert!(scheduler.cancel(id));
+        assert!(!scheduler.cancel(id));
+        assert!(scheduler.is_empty());
+        assert_eq!(scheduler.next_wakeup(), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "non-zero")]
+    fn test_zero_period() {
+        Scheduler::new().every(Duration::ZERO, ());
+    }
+}
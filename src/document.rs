@@ -0,0 +1,516 @@
+use std::mem;
+use std::ops::Range;
+
+use crate::error::Result;
+use crate::font::Fonts;
+use crate::layout::{LayoutEngine, LayoutTree};
+use crate::nodes::{Node, TextNode, View};
+use crate::style::{Color, Dimension, FlexDirection, Size};
+use crate::text::{line_height, WrapLines};
+
+/// One top-level element of a `Document`, with hints for where pages may break
+#[derive(Debug, Clone)]
+pub struct Block {
+    pub node: Node,
+    /// Never split this block; move it to the next page if it doesn't fit
+    pub keep_together: bool,
+    /// Always start this block on a new page
+    pub break_before: bool,
+    /// Don't end a page after this block, e.g. for headings
+    pub keep_with_next: bool,
+}
+
+impl Block {
+    pub fn new(node: impl Into<Node>) -> Self {
+        Self {
+            node: node.into(),
+            keep_together: false,
+            break_before: false,
+            keep_with_next: false,
+        }
+    }
+
+    pub fn keep_together(mut self) -> Self {
+        self.keep_together = true;
+        self
+    }
+
+    pub fn break_before(mut self) -> Self {
+        self.break_before = true;
+        self
+    }
+
+    pub fn keep_with_next(mut self) -> Self {
+        self.keep_with_next = true;
+        self
+    }
+}
+
+impl<T: Into<Node>> From<T> for Block {
+    fn from(node: T) -> Self {
+        Block::new(node)
+    }
+}
+
+/// A sequence of blocks laid out top to bottom and split into fixed-size pages.
+///
+/// Blocks that don't fit the rest of a page move to the next one. `Text`
+/// blocks are split between lines and column `View`/`ListView` blocks
+/// between children, unless they are marked `keep_together`.
+#[derive(Debug, Clone)]
+pub struct Document {
+    blocks: Vec<Block>,
+    page_size: Size,
+    padding: f32,
+    gap: f32,
+    background: Option<Color>,
+}
+
+impl Document {
+    pub fn new(page_size: Size) -> Self {
+        Self {
+            blocks: Vec::new(),
+            page_size,
+            padding: 0.0,
+            gap: 0.0,
+            background: None,
+        }
+    }
+
+    pub fn padding(mut self, px: f32) -> Self {
+        self.padding = px;
+        self
+    }
+
+    pub fn gap(mut self, px: f32) -> Self {
+        self.gap = px;
+        self
+    }
+
+    pub fn background(mut self, color: Color) -> Self {
+        self.background = Some(color);
+        self
+    }
+
+    pub fn block(mut self, block: impl Into<Block>) -> Self {
+        self.blocks.push(block.into());
+        self
+    }
+
+    pub fn blocks(mut self, blocks: impl IntoIterator<Item = impl Into<Block>>) -> Self {
+        for block in blocks {
+            self.blocks.push(block.into());
+        }
+        self
+    }
+
+    /// Split the document into pages, each a `View` of `page_size`.
+    /// There is always at least one page.
+    pub fn paginate(&self) -> Result<Vec<Node>> {
+        let content_width = (self.page_size.width - self.padding * 2.0).max(0.0);
+        let content_height = (self.page_size.height - self.padding * 2.0).max(0.0);
+
+        // lay every block out once at the page width to learn its height
+        let measure_root = View::column()
+            .width(Dimension::Px(content_width))
+            .children(self.blocks.iter().map(|block| block.node.clone()))
+            .build();
+        let layout =
+            LayoutEngine::new().compute(&measure_root, Size::new(content_width, f32::MAX))?;
+        let fonts = Fonts::new();
+
+        let pieces: Vec<Pieces> = layout
+            .children(0)
+            .iter()
+            .zip(&self.blocks)
+            .map(|(&index, block)| Pieces::of(block, &layout, index, &fonts))
+            .collect();
+
+        let mut pager = Pager::new(content_height, self.gap);
+        for (i, block) in self.blocks.iter().enumerate() {
+            if block.break_before {
+                pager.break_page();
+            }
+
+            let total = pieces[i].total();
+            if block.keep_with_next {
+                // the next block has to be able to start on the same page
+                let next = pieces
+                    .get(i + 1)
+                    .map_or(0.0, |next| self.gap + next.first());
+                if total + next > pager.remaining() && total <= pager.remaining() {
+                    pager.break_page();
+                }
+            }
+
+            pager.place(&block.node, &pieces[i]);
+        }
+
+        Ok(pager
+            .finish()
+            .into_iter()
+            .map(|nodes| self.page(nodes))
+            .collect())
+    }
+
+    fn page(&self, nodes: Vec<Node>) -> Node {
+        let mut page = View::column()
+            .width(Dimension::Px(self.page_size.width))
+            .height(Dimension::Px(self.page_size.height))
+            .padding(self.padding)
+            .gap(self.gap)
+            .children(nodes);
+        if let Some(color) = self.background {
+            page = page.background(color);
+        }
+        page.build()
+    }
+}
+
+/// Where a block can be split, as heights of the parts between break points
+#[derive(Debug)]
+enum Pieces {
+    Whole(f32),
+    /// Byte ranges of wrapped text lines of `line_height` each
+    Lines {
+        lines: Vec<Range<usize>>,
+        line_height: f32,
+    },
+    /// Top and bottom of each child, measured from the container's top,
+    /// and the container's padding
+    Children {
+        spans: Vec<(f32, f32)>,
+        padding: f32,
+    },
+}
+
+impl Pieces {
+    fn of(block: &Block, layout: &LayoutTree, index: usize, fonts: &Fonts) -> Self {
+        let rect = layout.nodes[index].rect;
+        if block.keep_together {
+            return Pieces::Whole(rect.height);
+        }
+
+        match &block.node {
+            Node::Text(text) => {
+                let font = fonts.get(text.font);
+                let lines: Vec<_> = WrapLines::new(
+                    font,
+                    &text.content,
+                    text.font_size,
+                    rect.width,
+                    text.wrap,
+                    0,
+                )
+                .collect();
+                if lines.len() < 2 {
+                    return Pieces::Whole(rect.height);
+                }
+                Pieces::Lines {
+                    lines,
+                    line_height: line_height(font, text.font_size),
+                }
+            }
+            Node::View(view) if view.direction == FlexDirection::Column => {
+                Self::children(layout, index, view.padding)
+            }
+            Node::ListView(list) if list.direction == FlexDirection::Column && !list.paged => {
+                Self::children(layout, index, list.padding)
+            }
+            _ => Pieces::Whole(rect.height),
+        }
+    }
+
+    fn children(layout: &LayoutTree, index: usize, padding: f32) -> Self {
+        let top = layout.nodes[index].rect.y;
+        let spans: Vec<(f32, f32)> = layout
+            .children(index)
+            .iter()
+            .map(|&child| {
+                let rect = layout.nodes[child].rect;
+                (rect.y - top, rect.y - top + rect.height)
+            })
+            .collect();
+        if spans.len() < 2 {
+            return Pieces::Whole(layout.nodes[index].rect.height);
+        }
+        Pieces::Children { spans, padding }
+    }
+
+    fn total(&self) -> f32 {
+        match self {
+            Pieces::Whole(height) => *height,
+            Pieces::Lines { lines, line_height } => lines.len() as f32 * line_height,
+            Pieces::Children { spans, padding } => {
+                spans.last().map_or(0.0, |span| span.1) + padding
+            }
+        }
+    }
+
+    /// Height of the smallest part that can start a page
+    fn first(&self) -> f32 {
+        match self {
+            Pieces::Whole(height) => *height,
+            Pieces::Lines { line_height, .. } => *line_height,
+            Pieces::Children { spans, padding } => spans[0].1 + padding,
+        }
+    }
+}
+
+/// Fills pages top to bottom
+struct Pager {
+    pages: Vec<Vec<Node>>,
+    current: Vec<Node>,
+    used: f32,
+    height: f32,
+    gap: f32,
+}
+
+impl Pager {
+    fn new(height: f32, gap: f32) -> Self {
+        Self {
+            pages: Vec::new(),
+            current: Vec::new(),
+            used: 0.0,
+            height,
+            gap,
+        }
+    }
+
+    /// Space left on the current page for the next node, after its gap
+    fn remaining(&self) -> f32 {
+        if self.current.is_empty() {
+            self.height
+        } else {
+            self.height - self.used - self.gap
+        }
+    }
+
+    fn push(&mut self, node: Node, height: f32) {
+        self.used = self.height - self.remaining() + height;
+        self.current.push(node);
+    }
+
+    fn break_page(&mut self) {
+        if !self.current.is_empty() {
+            self.pages.push(mem::take(&mut self.current));
+            self.used = 0.0;
+        }
+    }
+
+    fn place(&mut self, node: &Node, pieces: &Pieces) {
+        if pieces.total() <= self.remaining() {
+            self.push(node.clone(), pieces.total());
+            return;
+        }
+
+        match (node, pieces) {
+            (Node::Text(text), Pieces::Lines { lines, line_height }) => {
+                self.place_lines(text, lines, *line_height)
+            }
+            (_, Pieces::Children { spans, padding }) => self.place_children(node, spans, *padding),
+            _ => {
+                // a block taller than a page gets a page to itself and is clipped
+                self.break_page();
+                self.push(node.clone(), pieces.total());
+            }
+        }
+    }
+
+    fn place_lines(&mut self, text: &TextNode, lines: &[Range<usize>], line_height: f32) {
+        let mut rest = lines;
+        while !rest.is_empty() {
+            let mut fit = (self.remaining() / line_height).floor() as usize;
+            if fit == 0 {
+                if !self.current.is_empty() {
+                    self.break_page();
+                    continue;
+                }
+                // a line taller than the page still has to go somewhere
+                fit = 1;
+            }
+
+            let (taken, left) = rest.split_at(fit.min(rest.len()));
+            // the part is cut from the source, so it wraps into the same lines
+            let range = taken[0].start..taken[taken.len() - 1].end;
+            let part = TextNode {
+                content: text.content[range].to_string(),
+                ..text.clone()
+            };
+            self.push(Node::Text(part), taken.len() as f32 * line_height);
+
+            rest = left;
+            if !rest.is_empty() {
+                self.break_page();
+            }
+        }
+    }
+
+    fn place_children(&mut self, node: &Node, spans: &[(f32, f32)], padding: f32) {
+        let mut first = 0;
+        while first < spans.len() {
+            // the part starts `padding` above its first child
+            let top = spans[first].0 - padding;
+            let available = self.remaining();
+            let mut end = first;
+            while end < spans.len() && spans[end].1 + padding - top <= available {
+                end += 1;
+            }
+            if end == first {
+                if !self.current.is_empty() {
+                    self.break_page();
+                    continue;
+                }
+                end = first + 1;
+            }
+
+            let height = spans[end - 1].1 + padding - top;
+            self.push(with_children(node, first..end), height);
+
+            first = end;
+            if first < spans.len() {
+                self.break_page();
+            }
+        }
+    }
+
+    fn finish(mut self) -> Vec<Vec<Node>> {
+        self.break_page();
+        if self.pages.is_empty() {
+            self.pages.push(Vec::new());
+        }
+        self.pages
+    }
+}
+
+/// Copy of a container with only the children in `range`
+fn with_children(node: &Node, range: std::ops::Range<usize>) -> Node {
+    match node {
+        Node::View(view) => {
+            let mut view = view.clone();
+            view.children = view.children[range].to_vec();
+            Node::View(view)
+        }
+        Node::ListView(list) => {
+            let mut list = list.clone();
+            list.children = list.children[range.clone()].to_vec();
+            if list.item_ids.len() >= range.end {
+                list.item_ids = list.item_ids[range.clone()].to_vec();
+            }
+            list.selected_index = list
+                .selected_index
+                .and_then(|selected| selected.checked_sub(range.start))
+                .filter(|&selected| selected < range.len());
+            Node::ListView(list)
+        }
+        _ => node.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nodes::Text;
+    use crate::style::{TextFont, TextWrap};
+    use crate::test_util::block;
+    use crate::text::wrap_text;
+
+    fn page_children(page: &Node) -> usize {
+        page.children().len()
+    }
+
+    #[test]
+    fn test_blocks_move_to_next_page() {
+        let pages = Document::new(Size::new(200.0, 100.0))
+            .gap(10.0)
+            .blocks([block(40.0), block(40.0), block(40.0)])
+            .paginate()
+            .unwrap();
+
+        assert_eq!(pages.len(), 2);
+        assert_eq!(page_children(&pages[0]), 2);
+        assert_eq!(page_children(&pages[1]), 1);
+    }
+
+    #[test]
+    fn test_break_before_and_keep_with_next() {
+        let pages = Document::new(Size::new(200.0, 100.0))
+            .block(block(20.0))
+            .block(Block::new(block(20.0)).break_before())
+            .block(Block::new(block(20.0)).keep_with_next())
+            .block(block(70.0))
+            .paginate()
+            .unwrap();
+
+        // the heading follows its section onto the third page
+        assert_eq!(
+            pages.iter().map(page_children).collect::<Vec<_>>(),
+            [1, 1, 2]
+        );
+    }
+
+    #[test]
+    fn test_splits_char_wrapped_text_from_the_source() {
+        let fonts = Fonts::new();
+        let font = fonts.get(TextFont::NotosansRegular);
+        let content = "abcdefghijkl\nmnopqrstuvwx\n\n\nyz";
+        let width = 120.0;
+        let lines = wrap_text(font, content, 24.0, width, TextWrap::Char);
+        let page_lines = 2;
+        let height = line_height(font, 24.0) * page_lines as f32 + 1.0;
+
+        let pages = Document::new(Size::new(width, height))
+            .block(Text::new(content).wrap(TextWrap::Char).size(24.0))
+            .paginate()
+            .unwrap();
+        assert_eq!(pages.len(), lines.len().div_ceil(page_lines));
+
+        // each part wraps into the lines measured for it
+        let parts: Vec<Vec<String>> = pages
+            .iter()
+            .map(|page| match &page.children()[0] {
+                Node::Text(text) => {
+                    assert_eq!(text.wrap, TextWrap::Char);
+                    wrap_text(font, &text.content, 24.0, width, TextWrap::Char)
+                }
+                _ => panic!("expected a text node"),
+            })
+            .collect();
+        assert!(parts[..parts.len() - 1]
+            .iter()
+            .all(|part| part.len() == page_lines));
+        assert_eq!(parts.concat(), lines);
+    }
+
+    #[test]
+    fn test_splits_text_between_lines() {
+        let paragraph = Text::new("one two three four five six seven eight nine ten")
+            .wrap(TextWrap::Word)
+            .size(24.0);
+        let pages = Document::new(Size::new(120.0, 80.0))
+            .block(paragraph.clone())
+            .paginate()
+            .unwrap();
+        assert!(pages.len() > 1);
+
+        let kept = Document::new(Size::new(120.0, 80.0))
+            .block(block(10.0))
+            .block(Block::new(paragraph).keep_together())
+            .paginate()
+            .unwrap();
+        assert_eq!(page_children(&kept[0]), 1);
+    }
+
+    #[test]
+    fn test_splits_containers_between_children() {
+        let list = View::column().children((0..5).map(|_| block(30.0)));
+        let pages = Document::new(Size::new(200.0, 100.0))
+            .block(list)
+            .paginate()
+            .unwrap();
+
+        assert_eq!(pages.len(), 2);
+        assert_eq!(page_children(&pages[0].children()[0]), 3);
+        assert_eq!(page_children(&pages[1].children()[0]), 2);
+    }
+}
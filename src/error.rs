@@ -0,0 +1,94 @@
+use std::fmt;
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Debug)]
+pub enum Error {
+    /// Taffy rejected an operation while building or computing the layout
+    Layout(taffy::TaffyError),
+    /// The node tree has more nodes than the layout tree
+    MissingLayoutNode { index: usize },
+    /// The layout tree has more nodes than the node tree
+    ExtraLayoutNodes { expected: usize, found: usize },
+    /// The layout node at `index` was computed for a different kind of node
+    LayoutKindMismatch {
+        index: usize,
+        expected: &'static str,
+        found: &'static str,
+    },
+    /// The layout node at `index` has a different number of children
+    LayoutChildCountMismatch {
+        index: usize,
+        expected: usize,
+        found: usize,
+    },
+    /// The list at `index` is both paged and virtualized, which isn't
+    /// supported: pages need the geometry of every item
+    PagedVirtualList { index: usize },
+    /// Reading an input device failed
+    Input(std::io::Error),
+    /// Reading a config file, such as a keymap, failed
+    Config(std::io::Error),
+    /// A keymap config file has an invalid line
+    Keymap { line: usize, message: String },
+    /// The display device failed to flush a frame
+    Device(anyhow::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Layout(err) => write!(f, "layout failed: {}", err),
+            Error::MissingLayoutNode { index } => {
+                write!(f, "layout tree has no node at index {}", index)
+            }
+            Error::ExtraLayoutNodes { expected, found } => write!(
+                f,
+                "layout tree has {} nodes but the node tree has {}",
+                found, expected
+            ),
+            Error::LayoutKindMismatch {
+                index,
+                expected,
+                found,
+            } => write!(
+                f,
+                "layout node {} is a {} but the node tree has a {}",
+                index, found, expected
+            ),
+            Error::LayoutChildCountMismatch {
+                index,
+                expected,
+                found,
+            } => write!(
+                f,
+                "layout node {} has {} children but the node tree has {}",
+                index, found, expected
+            ),
+            Error::PagedVirtualList { index } => {
+                write!(f, "list node {} is both paged and virtualized", index)
+            }
+            Error::Input(err) => write!(f, "input failed: {}", err),
+            Error::Config(err) => write!(f, "reading config failed: {}", err),
+            Error::Keymap { line, message } => write!(f, "keymap line {}: {}", line, message),
+            Error::Device(err) => write!(f, "display failed: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Layout(err) => Some(err),
+            Error::Input(err) | Error::Config(err) => Some(err),
+            Error::Device(err) => Some(err.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+impl From<taffy::TaffyError> for Error {
+    fn from(err: taffy::TaffyError) -> Self {
+        Error::Layout(err)
+    }
+}
@@ -1,18 +1,62 @@
 
+use std::collections::{HashMap, HashSet};
+
+use crate::layout::LayoutTree;
 use crate::style::Rect;
 
 pub type FocusId = u32;
+pub type ScopeId = u32;
 
 #[derive(Debug, Clone)]
 pub struct FocusableRect {
     pub id: FocusId,
     pub rect: Rect,
+    /// Scopes this focusable is inside, outermost first
+    pub scopes: Vec<ScopeId>,
+}
+
+/// Direction for spatial focus navigation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FocusDirection {
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+impl FocusDirection {
+    fn is_horizontal(self) -> bool {
+        matches!(self, FocusDirection::Left | FocusDirection::Right)
+    }
+
+    /// Right and Down follow the registration order, Left and Up go back
+    fn is_forward(self) -> bool {
+        matches!(self, FocusDirection::Right | FocusDirection::Down)
+    }
+}
+
+/// A scope entered with `enter_scope` or `enter_trap`
+#[derive(Debug, Clone)]
+struct ActiveScope {
+    id: ScopeId,
+    trap: bool,
+    /// Focus when the scope was entered, restored by `exit_scope`
+    return_to: Option<FocusId>,
 }
 
 #[derive(Debug, Clone, Default)]
 pub struct FocusState {
     pub focused_id: Option<FocusId>,
     focusables: Vec<FocusableRect>,
+    /// Ids of the focusables taken from the last layout; the others were
+    /// registered by hand
+    from_layout: HashSet<FocusId>,
+    wrap: bool,
+    linear_fallback: bool,
+    /// Entered scopes, innermost last
+    scopes: Vec<ActiveScope>,
+    /// Focus inside each scope when it was last left
+    last_focused: HashMap<ScopeId, FocusId>,
 }
 
 impl FocusState {
@@ -20,63 +64,335 @@ impl FocusState {
         Self::default()
     }
 
+    /// Let directional moves past the last focusable wrap around to the
+    /// other side of the screen
+    pub fn wrap(mut self, wrap: bool) -> Self {
+        self.wrap = wrap;
+        self
+    }
+
+    /// When nothing lies in a direction, move to the next (Right/Down) or
+    /// previous (Left/Up) focusable in registration order instead
+    pub fn linear_fallback(mut self, fallback: bool) -> Self {
+        self.linear_fallback = fallback;
+        self
+    }
+
     pub fn register(&mut self, id: FocusId, rect: Rect) {
+        self.register_in(id, rect, &[]);
+    }
+
+    /// Register a focusable inside `scopes`, outermost first
+    pub fn register_in(&mut self, id: FocusId, rect: Rect, scopes: &[ScopeId]) {
+        self.focusables.retain(|f| f.id != id);
+        self.from_layout.remove(&id);
+        self.focusables.push(FocusableRect {
+            id,
+            rect,
+            scopes: scopes.to_vec(),
+        });
+    }
+
+    /// Replace the focusables of the previous layout with the `focusable`
+    /// nodes of `layout`. Focusables registered by hand stay, after the
+    /// layout's, unless a node takes over their id; remove them with
+    /// `unregister`. Entered scopes left without focusables are exited, so
+    /// removing a modal returns focus to where it was opened from; the focus
+    /// is dropped if its node is gone.
+    pub fn update_from_layout(&mut self, layout: &LayoutTree) {
+        let mut focusables = layout.focusables();
+        let from_layout: HashSet<FocusId> = focusables.iter().map(|f| f.id).collect();
+        focusables.extend(self.focusables.drain(..).filter(|f| {
+            !self.from_layout.contains(&f.id) && !from_layout.contains(&f.id)
+        }));
+        self.focusables = focusables;
+        self.from_layout = from_layout;
+
+        while !self.scopes.is_empty() && self.members().is_empty() {
+            self.exit_scope();
+        }
+        if let Some(id) = self.focused_id {
+            if !self.focusables.iter().any(|f| f.id == id) {
+                self.focused_id = None;
+            }
+        }
+    }
+
+    pub fn unregister(&mut self, id: FocusId) {
         self.focusables.retain(|f| f.id != id);
-        self.focusables.push(FocusableRect { id, rect });
+        self.from_layout.remove(&id);
     }
 
     pub fn clear_focusables(&mut self) {
         self.focusables.clear();
+        self.from_layout.clear();
     }
 
     pub fn focusables(&self) -> &[FocusableRect] {
         &self.focusables
     }
 
+    /// Focus the next focusable in registration order. Past the end, a
+    /// group scope is left and the enclosing level continues; the top level
+    /// and traps wrap around.
     pub fn focus_next(&mut self) {
-        if self.focusables.is_empty() {
-            self.focused_id = None;
+        self.focus_step(true);
+    }
+
+    pub fn focus_prev(&mut self) {
+        self.focus_step(false);
+    }
+
+    fn focus_step(&mut self, forward: bool) {
+        loop {
+            let members = self.members();
+            let position = self
+                .focused_id
+                .and_then(|id| self.position_in(&members, id));
+            let target = match position {
+                None if forward => members.first(),
+                None => members.last(),
+                Some(idx) if forward => members.get(idx + 1),
+                Some(idx) => idx.checked_sub(1).and_then(|idx| members.get(idx)),
+            };
+            if let Some(&target) = target {
+                self.focused_id = Some(self.focusables[target].id);
+                return;
+            }
+            if self.leave_group() {
+                continue;
+            }
+
+            let wrapped = if forward {
+                members.first()
+            } else {
+                members.last()
+            };
+            self.focused_id = wrapped.map(|&i| self.focusables[i].id);
             return;
         }
+    }
+
+    pub fn focus_left(&mut self) {
+        self.focus_direction(FocusDirection::Left);
+    }
+
+    pub fn focus_right(&mut self) {
+        self.focus_direction(FocusDirection::Right);
+    }
+
+    pub fn focus_up(&mut self) {
+        self.focus_direction(FocusDirection::Up);
+    }
+
+    pub fn focus_down(&mut self) {
+        self.focus_direction(FocusDirection::Down);
+    }
+
+    /// Move focus to the nearest focusable in `direction`.
+    ///
+    /// If nothing lies that way, a group scope is left and the search goes
+    /// on outside it. At the top level and in traps the linear fallback is
+    /// tried first and then wrapping; with neither enabled the focus stays
+    /// put.
+    pub fn focus_direction(&mut self, direction: FocusDirection) {
+        loop {
+            let members = self.members();
+            let current = self
+                .focused_id
+                .and_then(|id| self.position_in(&members, id))
+                .map(|idx| self.focusables[members[idx]].rect);
+            let Some(current) = current else {
+                // nothing focused yet, or the focus is outside the scope
+                self.focused_id = members.first().map(|&i| self.focusables[i].id);
+                return;
+            };
+
+            if let Some(id) = self.nearest(&members, &current, direction) {
+                self.focused_id = Some(id);
+                return;
+            }
+            if self.leave_group() {
+                continue;
+            }
 
-        self.focused_id = match self.focused_id {
-            None => self.focusables.first().map(|f| f.id),
-            Some(current) => {
-                let current_idx = self.focusables.iter().position(|f| f.id == current);
-                match current_idx {
-                    Some(idx) => {
-                        let next_idx = (idx + 1) % self.focusables.len();
-                        Some(self.focusables[next_idx].id)
-                    }
-                    None => self.focusables.first().map(|f| f.id),
+            if self.linear_fallback {
+                self.focus_linear(&members, direction);
+            } else if self.wrap {
+                let from = self.wrapped_origin(&members, &current, direction);
+                if let Some(id) = self.nearest(&members, &from, direction) {
+                    self.focused_id = Some(id);
                 }
             }
-        };
+            return;
+        }
     }
 
-    pub fn focus_prev(&mut self) {
-        if self.focusables.is_empty() {
-            self.focused_id = None;
+    /// The closest other member whose center lies beyond `from`'s center
+    /// in `direction`
+    fn nearest(
+        &self,
+        members: &[usize],
+        from: &Rect,
+        direction: FocusDirection,
+    ) -> Option<FocusId> {
+        let (from_x, from_y) = center(from);
+        members
+            .iter()
+            .map(|&i| &self.focusables[i])
+            .filter(|f| Some(f.id) != self.focused_id)
+            .filter_map(|f| {
+                let (x, y) = center(&f.rect);
+                let ahead = match direction {
+                    FocusDirection::Left => x < from_x,
+                    FocusDirection::Right => x > from_x,
+                    FocusDirection::Up => y < from_y,
+                    FocusDirection::Down => y > from_y,
+                };
+                ahead.then(|| (f.id, direction_score(from, &f.rect, direction)))
+            })
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(id, _)| id)
+    }
+
+    fn focus_linear(&mut self, members: &[usize], direction: FocusDirection) {
+        let Some(idx) = self.focused_id.and_then(|id| self.position_in(members, id)) else {
             return;
-        }
+        };
 
-        self.focused_id = match self.focused_id {
-            None => self.focusables.last().map(|f| f.id),
-            Some(current) => {
-                let current_idx = self.focusables.iter().position(|f| f.id == current);
-                match current_idx {
-                    Some(idx) => {
-                        let prev_idx = if idx == 0 {
-                            self.focusables.len() - 1
-                        } else {
-                            idx - 1
-                        };
-                        Some(self.focusables[prev_idx].id)
-                    }
-                    None => self.focusables.last().map(|f| f.id),
-                }
+        let len = members.len();
+        let target = if direction.is_forward() {
+            if idx + 1 < len {
+                Some(idx + 1)
+            } else {
+                self.wrap.then_some(0)
             }
+        } else if idx > 0 {
+            Some(idx - 1)
+        } else {
+            self.wrap.then_some(len - 1)
         };
+
+        if let Some(target) = target {
+            self.focused_id = Some(self.focusables[members[target]].id);
+        }
+    }
+
+    /// `from` moved just outside the bounds of all members on the side
+    /// opposite `direction`, keeping its position on the other axis
+    fn wrapped_origin(&self, members: &[usize], from: &Rect, direction: FocusDirection) -> Rect {
+        let rects = members.iter().map(|&i| &self.focusables[i].rect);
+        let mut origin = *from;
+        match direction {
+            FocusDirection::Right => {
+                origin.x = rects.map(|r| r.x).fold(f32::INFINITY, f32::min) - from.width
+            }
+            FocusDirection::Left => {
+                origin.x = rects
+                    .map(|r| r.x + r.width)
+                    .fold(f32::NEG_INFINITY, f32::max)
+            }
+            FocusDirection::Down => {
+                origin.y = rects.map(|r| r.y).fold(f32::INFINITY, f32::min) - from.height
+            }
+            FocusDirection::Up => {
+                origin.y = rects
+                    .map(|r| r.y + r.height)
+                    .fold(f32::NEG_INFINITY, f32::max)
+            }
+        }
+        origin
+    }
+
+    /// Restrict navigation to the focusables in `scope`, and focus the one
+    /// that had the focus when the scope was last left, or else its first.
+    ///
+    /// Navigating past the edge of the scope leaves it again, so groups
+    /// like toolbars can be stepped through and then left.
+    pub fn enter_scope(&mut self, scope: ScopeId) {
+        self.push_scope(scope, false);
+    }
+
+    /// Like `enter_scope`, but navigation wraps around inside the scope
+    /// instead of leaving it, for modal overlays. Only `exit_scope` leaves.
+    pub fn enter_trap(&mut self, scope: ScopeId) {
+        self.push_scope(scope, true);
+    }
+
+    /// Leave the innermost entered scope and focus what was focused when it
+    /// was entered, if that still exists. Returns the scope that was left.
+    pub fn exit_scope(&mut self) -> Option<ScopeId> {
+        let scope = self.pop_scope()?;
+        if let Some(id) = scope.return_to {
+            if self.focusables.iter().any(|f| f.id == id) {
+                self.focused_id = Some(id);
+            }
+        }
+        Some(scope.id)
+    }
+
+    /// The innermost entered scope
+    pub fn active_scope(&self) -> Option<ScopeId> {
+        self.scopes.last().map(|scope| scope.id)
+    }
+
+    /// True if the innermost entered scope is a trap
+    pub fn is_trapped(&self) -> bool {
+        self.scopes.last().is_some_and(|scope| scope.trap)
+    }
+
+    fn push_scope(&mut self, id: ScopeId, trap: bool) {
+        self.scopes.push(ActiveScope {
+            id,
+            trap,
+            return_to: self.focused_id,
+        });
+
+        let members = self.members();
+        let last = self
+            .last_focused
+            .get(&id)
+            .copied()
+            .filter(|&last| self.position_in(&members, last).is_some());
+        self.focused_id = last.or_else(|| members.first().map(|&i| self.focusables[i].id));
+    }
+
+    fn pop_scope(&mut self) -> Option<ActiveScope> {
+        let scope = self.scopes.pop()?;
+        let inside = self.focused_id.filter(|&id| {
+            self.focusables
+                .iter()
+                .any(|f| f.id == id && f.scopes.contains(&scope.id))
+        });
+        if let Some(id) = inside {
+            self.last_focused.insert(scope.id, id);
+        }
+        Some(scope)
+    }
+
+    /// Leave the innermost scope without moving the focus, unless it is a
+    /// trap or there is none. Returns whether a scope was left.
+    fn leave_group(&mut self) -> bool {
+        match self.scopes.last() {
+            Some(scope) if !scope.trap => self.pop_scope().is_some(),
+            _ => false,
+        }
+    }
+
+    /// Indices of the focusables navigation moves between: those inside the
+    /// innermost entered scope, or all of them
+    fn members(&self) -> Vec<usize> {
+        let scope = self.active_scope();
+        self.focusables
+            .iter()
+            .enumerate()
+            .filter(|(_, f)| scope.is_none_or(|scope| f.scopes.contains(&scope)))
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    fn position_in(&self, members: &[usize], id: FocusId) -> Option<usize> {
+        members.iter().position(|&i| self.focusables[i].id == id)
     }
 
     pub fn is_focused(&self, id: FocusId) -> bool {
@@ -92,8 +408,8 @@ impl FocusState {
     }
 
     pub fn focus_first_if_none(&mut self) {
-        if self.focused_id.is_none() && !self.focusables.is_empty() {
-            self.focused_id = self.focusables.first().map(|f| f.id);
+        if self.focused_id.is_none() {
+            self.focused_id = self.members().first().map(|&i| self.focusables[i].id);
         }
     }
 
@@ -104,14 +420,57 @@ impl FocusState {
     }
 }
 
+fn center(rect: &Rect) -> (f32, f32) {
+    (rect.x + rect.width / 2.0, rect.y + rect.height / 2.0)
+}
+
+/// Gap between the ranges `a` and `b`, zero if they overlap
+fn gap(a: (f32, f32), b: (f32, f32)) -> f32 {
+    (b.0 - a.1).max(a.0 - b.1).max(0.0)
+}
+
+/// Lower is closer. Distance along the direction counts once, misalignment
+/// across it twice, so a focusable in the same row or column wins over a
+/// nearer diagonal one.
+fn direction_score(from: &Rect, to: &Rect, direction: FocusDirection) -> f32 {
+    let horizontal = (from.x, from.x + from.width);
+    let vertical = (from.y, from.y + from.height);
+    let to_horizontal = (to.x, to.x + to.width);
+    let to_vertical = (to.y, to.y + to.height);
+
+    let (along, across, center_offset) = if direction.is_horizontal() {
+        (
+            gap(horizontal, to_horizontal),
+            gap(vertical, to_vertical),
+            (center(from).1 - center(to).1).abs(),
+        )
+    } else {
+        (
+            gap(vertical, to_vertical),
+            gap(horizontal, to_horizontal),
+            (center(from).0 - center(to).0).abs(),
+        )
+    };
+
+    // the center offset only breaks ties between aligned candidates
+    along + across * 2.0 + center_offset * 0.001
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::nodes::View;
+    use crate::test_util::block;
 
     fn make_rect() -> Rect {
         Rect { x: 0.0, y: 0.0, width: 100.0, height: 50.0 }
     }
 
+    /// A 100x50 focusable view
+    fn button(id: FocusId) -> View {
+        block(50.0).focusable(id)
+    }
+
     #[test]
     fn test_focus_navigation() {
         let mut state = FocusState::new();
@@ -147,4 +506,215 @@ mod tests {
         state.focus_prev();
         assert_eq!(state.focused_id, Some(2));
     }
+
+    fn cell(x: f32, y: f32) -> Rect {
+        Rect { x, y, width: 100.0, height: 50.0 }
+    }
+
+    /// A 3x2 grid of 100x50 cells with ids 1..=6 in reading order
+    fn grid() -> FocusState {
+        let mut state = FocusState::new();
+        for id in 1..=6 {
+            let col = ((id - 1) % 3) as f32;
+            let row = ((id - 1) / 3) as f32;
+            state.register(id, cell(col * 110.0, row * 60.0));
+        }
+        state
+    }
+
+    #[test]
+    fn test_focus_direction() {
+        let mut state = grid();
+        state.focus_right();
+        assert_eq!(state.focused_id, Some(1));
+
+        state.focus_right();
+        assert_eq!(state.focused_id, Some(2));
+        state.focus_down();
+        assert_eq!(state.focused_id, Some(5));
+        state.focus_left();
+        assert_eq!(state.focused_id, Some(4));
+        state.focus_up();
+        assert_eq!(state.focused_id, Some(1));
+
+        // nothing further left or up
+        state.focus_left();
+        state.focus_up();
+        assert_eq!(state.focused_id, Some(1));
+    }
+
+    #[test]
+    fn test_focus_direction_prefers_aligned() {
+        let mut state = FocusState::new();
+        state.register(1, cell(0.0, 0.0));
+        // nearer, but off to the side
+        state.register(2, cell(120.0, 60.0));
+        state.register(3, cell(0.0, 80.0));
+        state.set_focus(1);
+
+        state.focus_down();
+        assert_eq!(state.focused_id, Some(3));
+    }
+
+    #[test]
+    fn test_focus_direction_wrap() {
+        let mut state = grid().wrap(true);
+        state.set_focus(3);
+        state.focus_right();
+        assert_eq!(state.focused_id, Some(1));
+
+        state.set_focus(5);
+        state.focus_down();
+        assert_eq!(state.focused_id, Some(2));
+    }
+
+    #[test]
+    fn test_focus_direction_linear_fallback() {
+        let mut state = grid().linear_fallback(true);
+        state.set_focus(3);
+        state.focus_right();
+        assert_eq!(state.focused_id, Some(4));
+
+        state.focus_left();
+        assert_eq!(state.focused_id, Some(3));
+
+        // the end of the order only wraps when wrapping is on
+        state.set_focus(6);
+        state.focus_right();
+        assert_eq!(state.focused_id, Some(6));
+        let mut state = state.wrap(true);
+        state.focus_right();
+        assert_eq!(state.focused_id, Some(1));
+    }
+
+    #[test]
+    fn test_update_from_layout() {
+        use crate::layout::LayoutEngine;
+        use crate::nodes::ScrollView;
+        use crate::style::{Dimension, Size};
+
+        let screen = |ids: &[FocusId]| {
+            View::column()
+                .width(Dimension::Percent(1.0))
+                .child(button(1))
+                .child(
+                    ScrollView::vertical()
+                        .height(Dimension::Px(60.0))
+                        .scroll_offset(30.0)
+                        .children(ids.iter().map(|&id| button(id))),
+                )
+                .child(button(9).invisible())
+                .build()
+        };
+
+        let size = Size::new(200.0, 400.0);
+        let mut engine = LayoutEngine::new();
+        let mut state = FocusState::new();
+        let layout = engine.compute(&screen(&[2, 3]), size).unwrap();
+        state.update_from_layout(&layout);
+
+        let ids: Vec<FocusId> = state.focusables().iter().map(|f| f.id).collect();
+        assert_eq!(ids, [1, 2, 3]);
+        // rects are moved by the scroll offset
+        assert_eq!(state.focusables()[1].rect.y, 20.0);
+
+        state.set_focus(3);
+        let layout = engine.compute(&screen(&[2]), size).unwrap();
+        state.update_from_layout(&layout);
+        assert_eq!(state.focusables().len(), 2);
+        assert_eq!(state.focused_id, None);
+
+        // focusables registered by hand outlive layouts
+        state.register(20, make_rect());
+        let layout = engine.compute(&screen(&[2, 3]), size).unwrap();
+        state.update_from_layout(&layout);
+        let ids: Vec<FocusId> = state.focusables().iter().map(|f| f.id).collect();
+        assert_eq!(ids, [1, 2, 3, 20]);
+
+        state.unregister(20);
+        state.update_from_layout(&layout);
+        assert_eq!(state.focusables().len(), 3);
+    }
+
+    /// 1 and 4 on their own, 2 and 3 in scope 10
+    fn toolbar() -> FocusState {
+        let mut state = FocusState::new();
+        state.register(1, make_rect());
+        state.register_in(2, make_rect(), &[10]);
+        state.register_in(3, make_rect(), &[10]);
+        state.register(4, make_rect());
+        state
+    }
+
+    #[test]
+    fn test_group_scope() {
+        let mut state = toolbar();
+        state.set_focus(1);
+        state.enter_scope(10);
+        assert_eq!(state.focused_id, Some(2));
+
+        state.focus_next();
+        assert_eq!(state.focused_id, Some(3));
+        // stepping past the end leaves the group
+        state.focus_next();
+        assert_eq!(state.active_scope(), None);
+        assert_eq!(state.focused_id, Some(4));
+
+        // re-entering restores the member that had the focus
+        state.enter_scope(10);
+        assert_eq!(state.focused_id, Some(3));
+        state.exit_scope();
+        assert_eq!(state.focused_id, Some(4));
+    }
+
+    #[test]
+    fn test_trap_scope() {
+        let mut state = toolbar();
+        state.set_focus(4);
+        state.enter_trap(10);
+        assert!(state.is_trapped());
+
+        state.focus_next();
+        state.focus_next();
+        assert_eq!(state.focused_id, Some(2));
+        state.focus_prev();
+        assert_eq!(state.focused_id, Some(3));
+        state.focus_down();
+        assert_eq!(state.focused_id, Some(3));
+
+        assert_eq!(state.exit_scope(), Some(10));
+        assert_eq!(state.focused_id, Some(4));
+    }
+
+    #[test]
+    fn test_modal_removed_from_layout() {
+        use crate::layout::LayoutEngine;
+        use crate::style::Size;
+
+        let screen = |modal: bool| {
+            let root = View::column().child(button(1)).child(button(2));
+            if modal {
+                root.child(View::row().focus_scope(10).child(button(3)))
+                    .build()
+            } else {
+                root.build()
+            }
+        };
+
+        let size = Size::new(200.0, 400.0);
+        let mut engine = LayoutEngine::new();
+        let mut state = FocusState::new();
+        let layout = engine.compute(&screen(true), size).unwrap();
+        state.update_from_layout(&layout);
+        assert_eq!(state.focusables()[2].scopes, [10]);
+
+        state.set_focus(2);
+        state.enter_trap(10);
+        assert_eq!(state.focused_id, Some(3));
+
+        let layout = engine.compute(&screen(false), size).unwrap();
+        state.update_from_layout(&layout);
+        assert_eq!(state.active_scope(), None);
+        assert_eq!(state.focused_id, Some(2));
+    }
 }
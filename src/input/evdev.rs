@@ -0,0 +1,431 @@
+use std::collections::VecDeque;
+use std::ffi::c_long;
+use std::fs::{File, OpenOptions};
+use std::io::{self, ErrorKind, Read};
+use std::mem::size_of;
+use std::os::unix::fs::OpenOptionsExt;
+use std::os::unix::io::AsRawFd;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use crate::error::{Error, Result};
+use crate::render::Rotation;
+use crate::style::Size;
+
+use super::event::{InputEvent, KeyState, TouchPhase};
+use super::rotation::rotate_point;
+use super::source::InputSource;
+
+// event types and codes from linux/input-event-codes.h
+const EV_SYN: u16 = 0x00;
+const EV_KEY: u16 = 0x01;
+const EV_REL: u16 = 0x02;
+const EV_ABS: u16 = 0x03;
+const SYN_REPORT: u16 = 0x00;
+const BTN_TOUCH: u16 = 0x14a;
+const REL_DIAL: u16 = 0x07;
+const REL_WHEEL: u16 = 0x08;
+const ABS_X: u16 = 0x00;
+const ABS_Y: u16 = 0x01;
+const ABS_MT_SLOT: u16 = 0x2f;
+const ABS_MT_POSITION_X: u16 = 0x35;
+const ABS_MT_POSITION_Y: u16 = 0x36;
+const ABS_MT_TRACKING_ID: u16 = 0x39;
+
+/// `struct input_event`: a `timeval` of two longs, then type, code and value
+const EVENT_SIZE: usize = 2 * size_of::<c_long>() + 8;
+
+/// Reads key, touch and rotary events from Linux evdev devices such as
+/// `/dev/input/event0`.
+///
+/// The devices are read without blocking; `wait_event` sleeps in `poll(2)`
+/// until one of them has events or the timeout ends. Touch coordinates are
+/// scaled from the raw axis range to the panel size and then mapped through
+/// the display rotation.
+#[derive(Debug)]
+pub struct EvdevInput {
+    devices: Vec<(File, Decoder)>,
+    pending: VecDeque<InputEvent>,
+    mapping: TouchMapping,
+}
+
+impl EvdevInput {
+    pub fn open<P: AsRef<Path>>(paths: impl IntoIterator<Item = P>) -> Result<Self> {
+        let devices = paths
+            .into_iter()
+            .map(|path| {
+                let file = OpenOptions::new()
+                    .read(true)
+                    .custom_flags(libc::O_NONBLOCK)
+                    .open(path)
+                    .map_err(Error::Input)?;
+                Ok((file, Decoder::default()))
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(Self {
+            devices,
+            pending: VecDeque::new(),
+            mapping: TouchMapping::default(),
+        })
+    }
+
+    /// Largest raw touch coordinates the panel reports; without a range the
+    /// raw coordinates are taken as pixels
+    pub fn touch_range(mut self, max_x: f32, max_y: f32) -> Self {
+        self.mapping.range = Some((max_x, max_y));
+        self
+    }
+
+    /// Native size of the panel in pixels, before rotation
+    pub fn panel_size(mut self, size: Size) -> Self {
+        self.mapping.panel = size;
+        self
+    }
+
+    pub fn rotation(mut self, rotation: Rotation) -> Self {
+        self.mapping.rotation = rotation;
+        self
+    }
+
+    pub fn set_rotation(&mut self, rotation: Rotation) {
+        self.mapping.rotation = rotation;
+    }
+
+    fn read_devices(&mut self) -> Result<()> {
+        let mut buf = [0u8; EVENT_SIZE * 64];
+        for (file, decoder) in &mut self.devices {
+            loop {
+                let len = match file.read(&mut buf) {
+                    Ok(len) => len,
+                    Err(err) if err.kind() == ErrorKind::WouldBlock => break,
+                    Err(err) => return Err(Error::Input(err)),
+                };
+                for raw in buf[..len].chunks_exact(EVENT_SIZE) {
+                    let (kind, code, value) = parse_event(raw);
+                    decoder.decode(kind, code, value, &self.mapping, &mut self.pending);
+                }
+                if len < buf.len() {
+                    break;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Block until a device has events to read or `timeout` ends
+    fn wait_readable(&self, timeout: Option<Duration>) -> Result<()> {
+        let mut fds: Vec<libc::pollfd> = self
+            .devices
+            .iter()
+            .map(|(file, _)| libc::pollfd {
+                fd: file.as_raw_fd(),
+                events: libc::POLLIN,
+                revents: 0,
+            })
+            .collect();
+        // round up, so a wait shorter than a millisecond doesn't spin
+        let timeout = timeout.map_or(-1, |timeout| {
+            timeout.as_micros().div_ceil(1000).min(i32::MAX as u128) as i32
+        });
+
+        // SAFETY: `fds` is a valid array of `fds.len()` pollfd structs
+        let ready = unsafe { libc::poll(fds.as_mut_ptr(), fds.len() as libc::nfds_t, timeout) };
+        if ready < 0 {
+            let err = io::Error::last_os_error();
+            // a signal cut the wait short; the caller checks its deadline
+            if err.kind() != ErrorKind::Interrupted {
+                return Err(Error::Input(err));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl InputSource for EvdevInput {
+    fn poll_event(&mut self) -> Result<Option<InputEvent>> {
+        if self.pending.is_empty() {
+            self.read_devices()?;
+        }
+        Ok(self.pending.pop_front())
+    }
+
+    fn wait_event(&mut self, timeout: Option<Duration>) -> Result<Option<InputEvent>> {
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        loop {
+            if let Some(event) = self.poll_event()? {
+                return Ok(Some(event));
+            }
+            let timeout = match deadline {
+                Some(deadline) => match deadline.checked_duration_since(Instant::now()) {
+                    Some(left) if !left.is_zero() => Some(left),
+                    _ => return Ok(None),
+                },
+                None => None,
+            };
+            self.wait_readable(timeout)?;
+        }
+    }
+}
+
+/// Type, code and value of a raw `struct input_event`
+fn parse_event(raw: &[u8]) -> (u16, u16, i32) {
+    let at = EVENT_SIZE - 8;
+    let kind = u16::from_ne_bytes([raw[at], raw[at + 1]]);
+    let code = u16::from_ne_bytes([raw[at + 2], raw[at + 3]]);
+    let value = i32::from_ne_bytes([raw[at + 4], raw[at + 5], raw[at + 6], raw[at + 7]]);
+    (kind, code, value)
+}
+
+#[derive(Debug, Clone, Copy)]
+struct TouchMapping {
+    range: Option<(f32, f32)>,
+    panel: Size,
+    rotation: Rotation,
+}
+
+impl Default for TouchMapping {
+    fn default() -> Self {
+        Self {
+            range: None,
+            panel: Size::new(0.0, 0.0),
+            rotation: Rotation::Rotate0,
+        }
+    }
+}
+
+impl TouchMapping {
+    fn map(&self, x: f32, y: f32) -> (f32, f32) {
+        let (x, y) = match self.range {
+            Some((max_x, max_y)) => (x * self.panel.width / max_x, y * self.panel.height / max_y),
+            None => (x, y),
+        };
+        rotate_point(x, y, self.rotation, self.panel)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct Slot {
+    /// Set while a finger is on the panel
+    tracking: bool,
+    /// Whether a `Down` has been reported for the current contact
+    reported: bool,
+    moved: bool,
+    x: f32,
+    y: f32,
+}
+
+/// Turns the raw events of one device into `InputEvent`s. Touches are
+/// collected per multi-touch slot and reported on `SYN_REPORT`.
+#[derive(Debug, Default)]
+struct Decoder {
+    slot: usize,
+    slots: Vec<Slot>,
+    /// Set once the device sends multi-touch events; its single-touch
+    /// `ABS_X`/`BTN_TOUCH` events are then ignored
+    multitouch: bool,
+}
+
+impl Decoder {
+    fn decode(
+        &mut self,
+        kind: u16,
+        code: u16,
+        value: i32,
+        mapping: &TouchMapping,
+        out: &mut VecDeque<InputEvent>,
+    ) {
+        match (kind, code) {
+            (EV_SYN, SYN_REPORT) => self.report(mapping, out),
+            (EV_KEY, BTN_TOUCH) if !self.multitouch => {
+                self.slot = 0;
+                self.current().tracking = value != 0;
+            }
+            // the other touch tool buttons
+            (EV_KEY, 0x140..=0x14f) => {}
+            (EV_KEY, _) => {
+                let state = match value {
+                    0 => KeyState::Release,
+                    1 => KeyState::Press,
+                    _ => KeyState::Repeat,
+                };
+                out.push_back(InputEvent::Key { code, state });
+            }
+            (EV_REL, REL_DIAL | REL_WHEEL) => out.push_back(InputEvent::Rotary { delta: value }),
+            (EV_ABS, ABS_MT_SLOT) => {
+                self.multitouch = true;
+                self.slot = value.max(0) as usize;
+            }
+            (EV_ABS, ABS_MT_TRACKING_ID) => {
+                self.multitouch = true;
+                self.current().tracking = value >= 0;
+            }
+            (EV_ABS, ABS_MT_POSITION_X) => {
+                self.multitouch = true;
+                self.set_x(value);
+            }
+            (EV_ABS, ABS_MT_POSITION_Y) => {
+                self.multitouch = true;
+                self.set_y(value);
+            }
+            (EV_ABS, ABS_X) if !self.multitouch => {
+                self.slot = 0;
+                self.set_x(value);
+            }
+            (EV_ABS, ABS_Y) if !self.multitouch => {
+                self.slot = 0;
+                self.set_y(value);
+            }
+            _ => {}
+        }
+    }
+
+    fn current(&mut self) -> &mut Slot {
+        if self.slots.len() <= self.slot {
+            self.slots.resize(self.slot + 1, Slot::default());
+        }
+        &mut self.slots[self.slot]
+    }
+
+    fn set_x(&mut self, value: i32) {
+        let slot = self.current();
+        slot.x = value as f32;
+        slot.moved = true;
+    }
+
+    fn set_y(&mut self, value: i32) {
+        let slot = self.current();
+        slot.y = value as f32;
+        slot.moved = true;
+    }
+
+    fn report(&mut self, mapping: &TouchMapping, out: &mut VecDeque<InputEvent>) {
+        for (id, slot) in self.slots.iter_mut().enumerate() {
+            let phase = match (slot.tracking, slot.reported) {
+                (true, false) => TouchPhase::Down,
+                (true, true) if slot.moved => TouchPhase::Move,
+                (false, true) => TouchPhase::Up,
+                _ => continue,
+            };
+            slot.reported = slot.tracking;
+            slot.moved = false;
+
+            let (x, y) = mapping.map(slot.x, slot.y);
+            out.push_back(InputEvent::touch(id as u32, phase, x, y));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode(
+        decoder: &mut Decoder,
+        mapping: &TouchMapping,
+        events: &[(u16, u16, i32)],
+    ) -> Vec<InputEvent> {
+        let mut out = VecDeque::new();
+        for &(kind, code, value) in events {
+            decoder.decode(kind, code, value, mapping, &mut out);
+        }
+        out.into()
+    }
+
+    #[test]
+    fn test_parse_raw_event() {
+        let mut raw = vec![0u8; EVENT_SIZE - 8];
+        raw.extend_from_slice(&EV_KEY.to_ne_bytes());
+        raw.extend_from_slice(&28u16.to_ne_bytes());
+        raw.extend_from_slice(&1i32.to_ne_bytes());
+        assert_eq!(parse_event(&raw), (EV_KEY, 28, 1));
+    }
+
+    #[test]
+    fn test_decode_keys_and_rotary() {
+        let events = decode(
+            &mut Decoder::default(),
+            &TouchMapping::default(),
+            &[
+                (EV_KEY, 28, 1),
+                (EV_KEY, 28, 2),
+                (EV_KEY, 28, 0),
+                (EV_REL, REL_DIAL, -1),
+            ],
+        );
+        assert_eq!(
+            events,
+            [
+                InputEvent::key_press(28),
+                InputEvent::Key {
+                    code: 28,
+                    state: KeyState::Repeat
+                },
+                InputEvent::key_release(28),
+                InputEvent::Rotary { delta: -1 },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_decode_multitouch() {
+        let mapping = TouchMapping::default();
+        let mut decoder = Decoder::default();
+        let down = decode(
+            &mut decoder,
+            &mapping,
+            &[
+                (EV_ABS, ABS_MT_SLOT, 1),
+                (EV_ABS, ABS_MT_TRACKING_ID, 7),
+                (EV_ABS, ABS_MT_POSITION_X, 10),
+                (EV_ABS, ABS_MT_POSITION_Y, 20),
+                // legacy single-touch events are ignored
+                (EV_ABS, ABS_X, 10),
+                (EV_KEY, BTN_TOUCH, 1),
+                (EV_SYN, SYN_REPORT, 0),
+            ],
+        );
+        assert_eq!(down, [InputEvent::touch(1, TouchPhase::Down, 10.0, 20.0)]);
+
+        let rest = decode(
+            &mut decoder,
+            &mapping,
+            &[
+                (EV_ABS, ABS_MT_POSITION_X, 15),
+                (EV_SYN, SYN_REPORT, 0),
+                (EV_ABS, ABS_MT_TRACKING_ID, -1),
+                (EV_SYN, SYN_REPORT, 0),
+            ],
+        );
+        assert_eq!(
+            rest,
+            [
+                InputEvent::touch(1, TouchPhase::Move, 15.0, 20.0),
+                InputEvent::touch(1, TouchPhase::Up, 15.0, 20.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_decode_single_touch_with_rotation() {
+        let mapping = TouchMapping {
+            range: Some((1000.0, 1000.0)),
+            panel: Size::new(200.0, 100.0),
+            rotation: Rotation::Rotate180,
+        };
+        let events = decode(
+            &mut Decoder::default(),
+            &mapping,
+            &[
+                (EV_KEY, BTN_TOUCH, 1),
+                (EV_ABS, ABS_X, 250),
+                (EV_ABS, ABS_Y, 500),
+                (EV_SYN, SYN_REPORT, 0),
+            ],
+        );
+        assert_eq!(
+            events,
+            [InputEvent::touch(0, TouchPhase::Down, 150.0, 50.0)]
+        );
+    }
+}
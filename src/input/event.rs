@@ -0,0 +1,71 @@
+/// Linux input key code, as in `linux/input-event-codes.h`
+pub type KeyCode = u16;
+
+pub const KEY_ESC: KeyCode = 1;
+pub const KEY_ENTER: KeyCode = 28;
+pub const KEY_HOME: KeyCode = 102;
+pub const KEY_UP: KeyCode = 103;
+pub const KEY_PAGEUP: KeyCode = 104;
+pub const KEY_LEFT: KeyCode = 105;
+pub const KEY_RIGHT: KeyCode = 106;
+pub const KEY_DOWN: KeyCode = 108;
+pub const KEY_PAGEDOWN: KeyCode = 109;
+pub const KEY_POWER: KeyCode = 116;
+pub const KEY_MENU: KeyCode = 139;
+pub const KEY_BACK: KeyCode = 158;
+pub const KEY_SELECT: KeyCode = 0x161;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyState {
+    Press,
+    Release,
+    /// Auto-repeat while the key is held
+    Repeat,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TouchPhase {
+    Down,
+    Move,
+    Up,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum InputEvent {
+    Key {
+        code: KeyCode,
+        state: KeyState,
+    },
+    /// A touch point in screen coordinates, already mapped through the
+    /// display rotation. `id` tells fingers apart while they are down.
+    Touch {
+        id: u32,
+        phase: TouchPhase,
+        x: f32,
+        y: f32,
+    },
+    /// Steps of a rotary encoder or scroll wheel, positive clockwise
+    Rotary {
+        delta: i32,
+    },
+}
+
+impl InputEvent {
+    pub fn key_press(code: KeyCode) -> Self {
+        InputEvent::Key {
+            code,
+            state: KeyState::Press,
+        }
+    }
+
+    pub fn key_release(code: KeyCode) -> Self {
+        InputEvent::Key {
+            code,
+            state: KeyState::Release,
+        }
+    }
+
+    pub fn touch(id: u32, phase: TouchPhase, x: f32, y: f32) -> Self {
+        InputEvent::Touch { id, phase, x, y }
+    }
+}
@@ -0,0 +1,554 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use crate::layout::{HitTestResult, LayoutTree};
+
+use super::event::{InputEvent, TouchPhase};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SwipeDirection {
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Gesture {
+    Tap {
+        x: f32,
+        y: f32,
+    },
+    DoubleTap {
+        x: f32,
+        y: f32,
+    },
+    /// Reported while the finger is still down, once it has been held long
+    /// enough
+    LongPress {
+        x: f32,
+        y: f32,
+    },
+    /// `x` and `y` are where the swipe started
+    Swipe {
+        direction: SwipeDirection,
+        x: f32,
+        y: f32,
+        distance: f32,
+    },
+    /// Reported once, when the first of the two fingers lifts. `scale` is
+    /// the final finger distance over the starting one.
+    Pinch {
+        x: f32,
+        y: f32,
+        scale: f32,
+    },
+}
+
+impl Gesture {
+    /// Where the gesture happened: the start of a swipe, the center of a
+    /// pinch
+    pub fn position(&self) -> (f32, f32) {
+        match *self {
+            Gesture::Tap { x, y }
+            | Gesture::DoubleTap { x, y }
+            | Gesture::LongPress { x, y }
+            | Gesture::Swipe { x, y, .. }
+            | Gesture::Pinch { x, y, .. } => (x, y),
+        }
+    }
+
+    /// The node the gesture is aimed at
+    pub fn target<'a>(&self, layout: &'a LayoutTree) -> Option<HitTestResult<'a>> {
+        let (x, y) = self.position();
+        layout.hit_test(x, y)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Contact {
+    id: u32,
+    start: (f32, f32),
+    position: (f32, f32),
+    down_at: Instant,
+    /// Moved further than the tap slop since going down
+    moved: bool,
+    long_pressed: bool,
+}
+
+/// Turns touch events into gestures.
+///
+/// The defaults are tuned for e-ink panels: touch controllers there are
+/// coarse and a refresh takes hundreds of milliseconds, so the slop is
+/// generous and pinches are reported once at the end instead of on every
+/// move. Call `update` when `next_deadline` passes to deliver long presses,
+/// taps held back while waiting for a double tap, and gestures recognized
+/// behind another one; it returns one gesture per call.
+#[derive(Debug, Clone)]
+pub struct GestureRecognizer {
+    tap_slop: f32,
+    swipe_distance: f32,
+    long_press: Duration,
+    double_tap: Duration,
+    pinch_threshold: f32,
+    contacts: Vec<Contact>,
+    /// Distance between the fingers when the second one went down
+    pinch_start: Option<f32>,
+    /// Set once a second finger joins, until all fingers are up
+    multi_touch: bool,
+    /// A tap that may still become a double tap
+    pending_tap: Option<((f32, f32), Instant)>,
+    /// Gestures waiting for one reported before them, and when they were
+    /// recognized
+    queued: VecDeque<(Gesture, Instant)>,
+}
+
+impl Default for GestureRecognizer {
+    fn default() -> Self {
+        Self {
+            tap_slop: 20.0,
+            swipe_distance: 80.0,
+            long_press: Duration::from_millis(600),
+            double_tap: Duration::from_millis(300),
+            pinch_threshold: 0.1,
+            contacts: Vec::new(),
+            pinch_start: None,
+            multi_touch: false,
+            pending_tap: None,
+            queued: VecDeque::new(),
+        }
+    }
+}
+
+impl GestureRecognizer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// How far a finger may move and still tap or long-press
+    pub fn tap_slop(mut self, px: f32) -> Self {
+        self.tap_slop = px;
+        self
+    }
+
+    /// How far a finger must move to swipe
+    pub fn swipe_distance(mut self, px: f32) -> Self {
+        self.swipe_distance = px;
+        self
+    }
+
+    pub fn long_press(mut self, duration: Duration) -> Self {
+        self.long_press = duration;
+        self
+    }
+
+    /// Longest wait between two taps of a double tap. Taps are held back
+    /// this long; `Duration::ZERO` turns double taps off and reports taps
+    /// as soon as the finger lifts.
+    pub fn double_tap(mut self, interval: Duration) -> Self {
+        self.double_tap = interval;
+        self
+    }
+
+    /// Smallest change in scale that counts as a pinch
+    pub fn pinch_threshold(mut self, threshold: f32) -> Self {
+        self.pinch_threshold = threshold;
+        self
+    }
+
+    /// Feed an input event received at `now`. Events other than touches
+    /// are ignored.
+    pub fn handle(&mut self, event: &InputEvent, now: Instant) -> Option<Gesture> {
+        let InputEvent::Touch { id, phase, x, y } = *event else {
+            return None;
+        };
+
+        let gesture = match phase {
+            TouchPhase::Down => self.touch_down(id, (x, y), now),
+            TouchPhase::Move => {
+                self.touch_move(id, (x, y));
+                None
+            }
+            TouchPhase::Up => {
+                self.touch_move(id, (x, y));
+                self.touch_up(id, now)
+            }
+        };
+        // gestures are reported in the order they were recognized
+        if let Some(gesture) = gesture {
+            self.queued.push_back((gesture, now));
+        }
+        self.queued.pop_front().map(|(gesture, _)| gesture)
+    }
+
+    /// Report gestures that are due at `now` without a new event
+    pub fn update(&mut self, now: Instant) -> Option<Gesture> {
+        if let Some((gesture, _)) = self.queued.pop_front() {
+            return Some(gesture);
+        }
+
+        if let Some(((x, y), at)) = self.pending_tap {
+            if now >= at + self.double_tap {
+                self.pending_tap = None;
+                return Some(Gesture::Tap { x, y });
+            }
+        }
+
+        if self.multi_touch {
+            return None;
+        }
+        let long_press = self.long_press;
+        let contact = self.contacts.first_mut()?;
+        if !contact.moved && !contact.long_pressed && now >= contact.down_at + long_press {
+            contact.long_pressed = true;
+            let (x, y) = contact.start;
+            return Some(Gesture::LongPress { x, y });
+        }
+        None
+    }
+
+    /// When `update` next has something to report, if anything
+    pub fn next_deadline(&self) -> Option<Instant> {
+        if let Some(&(_, at)) = self.queued.front() {
+            return Some(at);
+        }
+        let tap = self.pending_tap.map(|(_, at)| at + self.double_tap);
+        let long_press = match self.contacts.as_slice() {
+            [contact] if !self.multi_touch && !contact.moved && !contact.long_pressed => {
+                Some(contact.down_at + self.long_press)
+            }
+            _ => None,
+        };
+        match (tap, long_press) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        }
+    }
+
+    fn touch_down(&mut self, id: u32, position: (f32, f32), now: Instant) -> Option<Gesture> {
+        // a tap whose double-tap window has passed
+        let expired = match self.pending_tap {
+            Some(((x, y), at)) if now >= at + self.double_tap => {
+                self.pending_tap = None;
+                Some(Gesture::Tap { x, y })
+            }
+            _ => None,
+        };
+
+        self.contacts.retain(|c| c.id != id);
+        self.contacts.push(Contact {
+            id,
+            start: position,
+            position,
+            down_at: now,
+            moved: false,
+            long_pressed: false,
+        });
+        if self.contacts.len() == 2 {
+            self.multi_touch = true;
+            self.pinch_start = Some(self.finger_distance());
+        }
+
+        expired
+    }
+
+    fn touch_move(&mut self, id: u32, position: (f32, f32)) {
+        let slop = self.tap_slop;
+        if let Some(contact) = self.contacts.iter_mut().find(|c| c.id == id) {
+            contact.position = position;
+            if distance(contact.start, position) > slop {
+                contact.moved = true;
+            }
+        }
+    }
+
+    fn touch_up(&mut self, id: u32, now: Instant) -> Option<Gesture> {
+        let index = self.contacts.iter().position(|c| c.id == id)?;
+
+        if self.multi_touch {
+            let gesture = self.pinch_start.take().and_then(|start| {
+                let ((x0, y0), (x1, y1)) = self.finger_positions()?;
+                let scale = self.finger_distance() / start.max(1.0);
+                ((scale - 1.0).abs() >= self.pinch_threshold).then_some(Gesture::Pinch {
+                    x: (x0 + x1) / 2.0,
+                    y: (y0 + y1) / 2.0,
+                    scale,
+                })
+            });
+            self.contacts.remove(index);
+            if self.contacts.is_empty() {
+                self.multi_touch = false;
+            }
+            if let Some(gesture) = gesture {
+                self.queue_after_pending_tap(gesture, now);
+            }
+            return None;
+        }
+
+        let contact = self.contacts.remove(index);
+        if contact.long_pressed {
+            return None;
+        }
+
+        let (x, y) = contact.start;
+        let dx = contact.position.0 - x;
+        let dy = contact.position.1 - y;
+        let moved = distance(contact.start, contact.position);
+        if moved >= self.swipe_distance {
+            let direction = if dx.abs() >= dy.abs() {
+                if dx > 0.0 {
+                    SwipeDirection::Right
+                } else {
+                    SwipeDirection::Left
+                }
+            } else if dy > 0.0 {
+                SwipeDirection::Down
+            } else {
+                SwipeDirection::Up
+            };
+            let swipe = Gesture::Swipe {
+                direction,
+                x,
+                y,
+                distance: moved,
+            };
+            self.queue_after_pending_tap(swipe, now);
+            return None;
+        }
+        if contact.moved {
+            return None;
+        }
+
+        match self.pending_tap.take() {
+            Some((first, at))
+                if now < at + self.double_tap && distance(first, (x, y)) <= self.tap_slop * 2.0 =>
+            {
+                Some(Gesture::DoubleTap { x, y })
+            }
+            _ if self.double_tap.is_zero() => Some(Gesture::Tap { x, y }),
+            // an earlier tap this one can't pair with is reported now, and
+            // this one waits in its place
+            previous => {
+                self.pending_tap = Some(((x, y), now));
+                previous.map(|((x, y), _)| Gesture::Tap { x, y })
+            }
+        }
+    }
+
+    /// Queue `gesture`, after a pending tap that can no longer become a
+    /// double tap
+    fn queue_after_pending_tap(&mut self, gesture: Gesture, now: Instant) {
+        if let Some(((x, y), _)) = self.pending_tap.take() {
+            self.queued.push_back((Gesture::Tap { x, y }, now));
+        }
+        self.queued.push_back((gesture, now));
+    }
+
+    fn finger_positions(&self) -> Option<((f32, f32), (f32, f32))> {
+        match self.contacts.as_slice() {
+            [a, b, ..] => Some((a.position, b.position)),
+            _ => None,
+        }
+    }
+
+    fn finger_distance(&self) -> f32 {
+        self.finger_positions().map_or(0.0, |(a, b)| distance(a, b))
+    }
+}
+
+fn distance(a: (f32, f32), b: (f32, f32)) -> f32 {
+    ((b.0 - a.0).powi(2) + (b.1 - a.1).powi(2)).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn touch(id: u32, phase: TouchPhase, x: f32, y: f32) -> InputEvent {
+        InputEvent::touch(id, phase, x, y)
+    }
+
+    /// Feed `events` at the given milliseconds after `start`, collecting
+    /// what is recognized
+    fn run(recognizer: &mut GestureRecognizer, events: &[(u64, InputEvent)]) -> Vec<Gesture> {
+        let start = Instant::now();
+        events
+            .iter()
+            .filter_map(|(ms, event)| recognizer.handle(event, start + Duration::from_millis(*ms)))
+            .collect()
+    }
+
+    #[test]
+    fn test_tap_and_double_tap() {
+        let start = Instant::now();
+        let mut recognizer = GestureRecognizer::new();
+        recognizer.handle(&touch(0, TouchPhase::Down, 10.0, 10.0), start);
+        assert_eq!(
+            recognizer.handle(&touch(0, TouchPhase::Up, 12.0, 10.0), start),
+            None
+        );
+
+        // held back until the double-tap window closes
+        let deadline = recognizer.next_deadline().unwrap();
+        assert_eq!(
+            recognizer.update(deadline),
+            Some(Gesture::Tap { x: 10.0, y: 10.0 })
+        );
+
+        let gestures = run(
+            &mut recognizer,
+            &[
+                (0, touch(0, TouchPhase::Down, 10.0, 10.0)),
+                (50, touch(0, TouchPhase::Up, 10.0, 10.0)),
+                (150, touch(0, TouchPhase::Down, 14.0, 10.0)),
+                (200, touch(0, TouchPhase::Up, 14.0, 10.0)),
+            ],
+        );
+        assert_eq!(gestures, [Gesture::DoubleTap { x: 14.0, y: 10.0 }]);
+    }
+
+    #[test]
+    fn test_taps_far_apart() {
+        let start = Instant::now();
+        let mut recognizer = GestureRecognizer::new();
+        let gestures = run(
+            &mut recognizer,
+            &[
+                (0, touch(0, TouchPhase::Down, 10.0, 10.0)),
+                (50, touch(0, TouchPhase::Up, 10.0, 10.0)),
+                (100, touch(0, TouchPhase::Down, 150.0, 10.0)),
+                (150, touch(0, TouchPhase::Up, 150.0, 10.0)),
+            ],
+        );
+        // the first tap is reported when the second can't pair with it
+        assert_eq!(gestures, [Gesture::Tap { x: 10.0, y: 10.0 }]);
+        assert_eq!(
+            recognizer.update(start + Duration::from_secs(1)),
+            Some(Gesture::Tap { x: 150.0, y: 10.0 })
+        );
+    }
+
+    #[test]
+    fn test_swipe_after_pending_tap() {
+        let mut recognizer = GestureRecognizer::new();
+        let gestures = run(
+            &mut recognizer,
+            &[
+                (0, touch(0, TouchPhase::Down, 10.0, 10.0)),
+                (50, touch(0, TouchPhase::Up, 10.0, 10.0)),
+                (100, touch(0, TouchPhase::Down, 300.0, 100.0)),
+                (150, touch(0, TouchPhase::Up, 100.0, 100.0)),
+            ],
+        );
+        assert_eq!(gestures, [Gesture::Tap { x: 10.0, y: 10.0 }]);
+
+        // the swipe is due as soon as the tap has been reported
+        let deadline = recognizer.next_deadline().unwrap();
+        assert!(matches!(
+            recognizer.update(deadline),
+            Some(Gesture::Swipe {
+                direction: SwipeDirection::Left,
+                ..
+            })
+        ));
+        assert_eq!(recognizer.update(deadline), None);
+    }
+
+    #[test]
+    fn test_tap_without_double_tap() {
+        let mut recognizer = GestureRecognizer::new().double_tap(Duration::ZERO);
+        let gestures = run(
+            &mut recognizer,
+            &[
+                (0, touch(0, TouchPhase::Down, 10.0, 10.0)),
+                (50, touch(0, TouchPhase::Up, 10.0, 10.0)),
+            ],
+        );
+        assert_eq!(gestures, [Gesture::Tap { x: 10.0, y: 10.0 }]);
+    }
+
+    #[test]
+    fn test_long_press() {
+        let start = Instant::now();
+        let mut recognizer = GestureRecognizer::new();
+        recognizer.handle(&touch(0, TouchPhase::Down, 10.0, 10.0), start);
+        assert_eq!(recognizer.update(start + Duration::from_millis(100)), None);
+
+        let deadline = recognizer.next_deadline().unwrap();
+        assert_eq!(
+            recognizer.update(deadline),
+            Some(Gesture::LongPress { x: 10.0, y: 10.0 })
+        );
+        // lifting afterwards is not a tap
+        assert_eq!(
+            recognizer.handle(&touch(0, TouchPhase::Up, 10.0, 10.0), deadline),
+            None
+        );
+        assert_eq!(recognizer.next_deadline(), None);
+    }
+
+    #[test]
+    fn test_swipe() {
+        let mut recognizer = GestureRecognizer::new();
+        let gestures = run(
+            &mut recognizer,
+            &[
+                (0, touch(0, TouchPhase::Down, 300.0, 100.0)),
+                (50, touch(0, TouchPhase::Move, 200.0, 110.0)),
+                (100, touch(0, TouchPhase::Up, 100.0, 120.0)),
+            ],
+        );
+        assert!(matches!(
+            gestures[..],
+            [Gesture::Swipe {
+                direction: SwipeDirection::Left,
+                x: 300.0,
+                y: 100.0,
+                ..
+            }]
+        ));
+    }
+
+    #[test]
+    fn test_pinch() {
+        let mut recognizer = GestureRecognizer::new();
+        let gestures = run(
+            &mut recognizer,
+            &[
+                (0, touch(0, TouchPhase::Down, 100.0, 100.0)),
+                (10, touch(1, TouchPhase::Down, 200.0, 100.0)),
+                (50, touch(0, TouchPhase::Move, 50.0, 100.0)),
+                (60, touch(1, TouchPhase::Move, 250.0, 100.0)),
+                (100, touch(1, TouchPhase::Up, 250.0, 100.0)),
+                (110, touch(0, TouchPhase::Up, 50.0, 100.0)),
+            ],
+        );
+        assert_eq!(
+            gestures,
+            [Gesture::Pinch {
+                x: 150.0,
+                y: 100.0,
+                scale: 2.0
+            }]
+        );
+    }
+
+    #[test]
+    fn test_gesture_target() {
+        use crate::layout::LayoutEngine;
+        use crate::nodes::View;
+        use crate::style::{Dimension, Size};
+
+        let root = View::column()
+            .width(Dimension::Percent(1.0))
+            .child(View::new().height(Dimension::Px(50.0)).key("header"))
+            .child(View::new().height(Dimension::Px(50.0)).key("page"))
+            .build();
+        let layout = LayoutEngine::new()
+            .compute(&root, Size::new(200.0, 200.0))
+            .unwrap();
+
+        let tap = Gesture::Tap { x: 10.0, y: 60.0 };
+        assert_eq!(tap.target(&layout).unwrap().key, Some("page"));
+    }
+}
@@ -0,0 +1,286 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::error::{Error, Result};
+use crate::focus::{FocusDirection, FocusState};
+
+use super::event::*;
+
+/// What an input means to the app, independent of the key that sent it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Action {
+    NextPage,
+    PrevPage,
+    Select,
+    Back,
+    Menu,
+    FocusLeft,
+    FocusRight,
+    FocusUp,
+    FocusDown,
+    FocusNext,
+    FocusPrev,
+}
+
+const ACTIONS: [(&str, Action); 11] = [
+    ("NextPage", Action::NextPage),
+    ("PrevPage", Action::PrevPage),
+    ("Select", Action::Select),
+    ("Back", Action::Back),
+    ("Menu", Action::Menu),
+    ("FocusLeft", Action::FocusLeft),
+    ("FocusRight", Action::FocusRight),
+    ("FocusUp", Action::FocusUp),
+    ("FocusDown", Action::FocusDown),
+    ("FocusNext", Action::FocusNext),
+    ("FocusPrev", Action::FocusPrev),
+];
+
+const KEY_NAMES: [(&str, KeyCode); 13] = [
+    ("KEY_ESC", KEY_ESC),
+    ("KEY_ENTER", KEY_ENTER),
+    ("KEY_HOME", KEY_HOME),
+    ("KEY_UP", KEY_UP),
+    ("KEY_PAGEUP", KEY_PAGEUP),
+    ("KEY_LEFT", KEY_LEFT),
+    ("KEY_RIGHT", KEY_RIGHT),
+    ("KEY_DOWN", KEY_DOWN),
+    ("KEY_PAGEDOWN", KEY_PAGEDOWN),
+    ("KEY_POWER", KEY_POWER),
+    ("KEY_MENU", KEY_MENU),
+    ("KEY_BACK", KEY_BACK),
+    ("KEY_SELECT", KEY_SELECT),
+];
+
+impl Action {
+    /// Parse an action name such as `NextPage`, `next_page` or `next-page`
+    pub fn from_name(name: &str) -> Option<Action> {
+        let name: String = name.chars().filter(|c| !matches!(c, '_' | '-')).collect();
+        ACTIONS
+            .iter()
+            .find(|(action, _)| action.eq_ignore_ascii_case(&name))
+            .map(|&(_, action)| action)
+    }
+
+    /// The direction of the directional focus actions
+    pub fn focus_direction(self) -> Option<FocusDirection> {
+        match self {
+            Action::FocusLeft => Some(FocusDirection::Left),
+            Action::FocusRight => Some(FocusDirection::Right),
+            Action::FocusUp => Some(FocusDirection::Up),
+            Action::FocusDown => Some(FocusDirection::Down),
+            _ => None,
+        }
+    }
+}
+
+/// Maps key codes and rotary steps to `Action`s.
+///
+/// Keymaps can be loaded from a config file with one `key = action` binding
+/// per line. Keys are evdev codes, as numbers or names like `KEY_PAGEDOWN`;
+/// `rotary_cw` and `rotary_ccw` bind the two turning directions of a dial.
+/// Lines starting with `#` are comments.
+///
+/// ```text
+/// # page turn buttons on rev B boards
+/// KEY_PAGEDOWN = NextPage
+/// 193 = PrevPage
+/// rotary_cw = FocusNext
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Keymap {
+    keys: HashMap<KeyCode, Action>,
+    rotary_cw: Option<Action>,
+    rotary_ccw: Option<Action>,
+}
+
+impl Keymap {
+    /// A keymap with no bindings
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Arrows move the focus, Enter selects, Esc/Back go back, Page Up/Down
+    /// turn pages and a dial steps through the focus order
+    pub fn standard() -> Self {
+        Self::new()
+            .bind(KEY_LEFT, Action::FocusLeft)
+            .bind(KEY_RIGHT, Action::FocusRight)
+            .bind(KEY_UP, Action::FocusUp)
+            .bind(KEY_DOWN, Action::FocusDown)
+            .bind(KEY_ENTER, Action::Select)
+            .bind(KEY_SELECT, Action::Select)
+            .bind(KEY_ESC, Action::Back)
+            .bind(KEY_BACK, Action::Back)
+            .bind(KEY_MENU, Action::Menu)
+            .bind(KEY_PAGEDOWN, Action::NextPage)
+            .bind(KEY_PAGEUP, Action::PrevPage)
+            .rotary(Action::FocusNext, Action::FocusPrev)
+    }
+
+    pub fn bind(mut self, code: KeyCode, action: Action) -> Self {
+        self.keys.insert(code, action);
+        self
+    }
+
+    /// Actions for turning a dial clockwise and counter-clockwise
+    pub fn rotary(mut self, clockwise: Action, counter_clockwise: Action) -> Self {
+        self.rotary_cw = Some(clockwise);
+        self.rotary_ccw = Some(counter_clockwise);
+        self
+    }
+
+    /// A keymap with only the bindings of a config file; use `merge` to
+    /// apply a file on top of `standard`
+    pub fn parse(config: &str) -> Result<Self> {
+        Self::new().merge(config)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let config = std::fs::read_to_string(path).map_err(Error::Config)?;
+        Self::parse(&config)
+    }
+
+    /// Add the bindings of a config file, replacing existing ones for the
+    /// same keys
+    pub fn merge(mut self, config: &str) -> Result<Self> {
+        for (index, line) in config.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let invalid = |message: String| Error::Keymap {
+                line: index + 1,
+                message,
+            };
+
+            let (key, action) = line
+                .split_once('=')
+                .ok_or_else(|| invalid("expected `key = action`".to_string()))?;
+            let (key, action) = (key.trim(), action.trim());
+            let action = Action::from_name(action)
+                .ok_or_else(|| invalid(format!("unknown action `{}`", action)))?;
+
+            match key {
+                "rotary_cw" => self.rotary_cw = Some(action),
+                "rotary_ccw" => self.rotary_ccw = Some(action),
+                _ => {
+                    let code =
+                        key_code(key).ok_or_else(|| invalid(format!("unknown key `{}`", key)))?;
+                    self.keys.insert(code, action);
+                }
+            }
+        }
+        Ok(self)
+    }
+
+    /// The action an event maps to. Key presses and repeats map through the
+    /// bindings, releases map to nothing.
+    pub fn action(&self, event: &InputEvent) -> Option<Action> {
+        match *event {
+            InputEvent::Key {
+                code,
+                state: KeyState::Press | KeyState::Repeat,
+            } => self.keys.get(&code).copied(),
+            InputEvent::Rotary { delta } if delta > 0 => self.rotary_cw,
+            InputEvent::Rotary { delta } if delta < 0 => self.rotary_ccw,
+            _ => None,
+        }
+    }
+
+    /// Map an event and move the focus for the focus actions. Returns the
+    /// actions left for the app to handle.
+    pub fn dispatch(&self, event: &InputEvent, focus: &mut FocusState) -> Option<Action> {
+        let action = self.action(event)?;
+        match action {
+            Action::FocusNext => focus.focus_next(),
+            Action::FocusPrev => focus.focus_prev(),
+            _ => match action.focus_direction() {
+                Some(direction) => focus.focus_direction(direction),
+                None => return Some(action),
+            },
+        }
+        None
+    }
+}
+
+/// A key code given as a number or a `KEY_*` name
+fn key_code(name: &str) -> Option<KeyCode> {
+    if let Ok(code) = name.parse() {
+        return Some(code);
+    }
+    KEY_NAMES
+        .iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case(name))
+        .map(|&(_, code)| code)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::style::Rect;
+
+    #[test]
+    fn test_parse_config() {
+        let keymap = Keymap::parse(
+            "# rev B\n\
+             KEY_PAGEDOWN = NextPage\n\
+             193 = prev_page\n\
+             \n\
+             rotary_cw = FocusNext\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            keymap.action(&InputEvent::key_press(KEY_PAGEDOWN)),
+            Some(Action::NextPage)
+        );
+        assert_eq!(
+            keymap.action(&InputEvent::key_press(193)),
+            Some(Action::PrevPage)
+        );
+        assert_eq!(keymap.action(&InputEvent::key_release(193)), None);
+        assert_eq!(
+            keymap.action(&InputEvent::Rotary { delta: 2 }),
+            Some(Action::FocusNext)
+        );
+        assert_eq!(keymap.action(&InputEvent::Rotary { delta: -1 }), None);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let err = Keymap::parse("KEY_ENTER = Select\nKEY_NOPE = Back").unwrap_err();
+        assert!(matches!(err, Error::Keymap { line: 2, .. }));
+        assert!(Keymap::parse("KEY_ENTER Select").is_err());
+        assert!(Keymap::parse("KEY_ENTER = Jump").is_err());
+    }
+
+    #[test]
+    fn test_dispatch_moves_focus() {
+        let mut focus = FocusState::new();
+        for id in 1..=2 {
+            let x = id as f32 * 100.0;
+            focus.register(
+                id,
+                Rect {
+                    x,
+                    y: 0.0,
+                    width: 50.0,
+                    height: 50.0,
+                },
+            );
+        }
+        focus.set_focus(1);
+
+        let keymap = Keymap::standard();
+        assert_eq!(
+            keymap.dispatch(&InputEvent::key_press(KEY_RIGHT), &mut focus),
+            None
+        );
+        assert_eq!(focus.focused_id, Some(2));
+        assert_eq!(
+            keymap.dispatch(&InputEvent::key_press(KEY_ENTER), &mut focus),
+            Some(Action::Select)
+        );
+    }
+}
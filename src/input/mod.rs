@@ -0,0 +1,15 @@
+#[cfg(target_os = "linux")]
+mod evdev;
+mod event;
+mod gesture;
+mod keymap;
+mod rotation;
+mod source;
+
+#[cfg(target_os = "linux")]
+pub use evdev::EvdevInput;
+pub use event::*;
+pub use gesture::{Gesture, GestureRecognizer, SwipeDirection};
+pub use keymap::{Action, Keymap};
+pub use rotation::rotate_point;
+pub use source::{InputSource, ScriptedInput};
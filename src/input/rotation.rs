@@ -0,0 +1,47 @@
+use crate::render::Rotation;
+use crate::style::Size;
+
+/// Map a point from the panel's native orientation to screen coordinates
+/// when the content is drawn rotated clockwise by `rotation`.
+///
+/// `panel` is the native size of the panel; for `Rotate90` and `Rotate270`
+/// the screen is `panel` with width and height swapped.
+pub fn rotate_point(x: f32, y: f32, rotation: Rotation, panel: Size) -> (f32, f32) {
+    match rotation {
+        Rotation::Rotate0 => (x, y),
+        Rotation::Rotate90 => (y, panel.width - x),
+        Rotation::Rotate180 => (panel.width - x, panel.height - y),
+        Rotation::Rotate270 => (panel.height - y, x),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rotate_corners() {
+        let panel = Size::new(100.0, 50.0);
+        // the panel's top-right corner is the screen's origin at 90 degrees
+        assert_eq!(
+            rotate_point(100.0, 0.0, Rotation::Rotate90, panel),
+            (0.0, 0.0)
+        );
+        assert_eq!(
+            rotate_point(0.0, 0.0, Rotation::Rotate90, panel),
+            (0.0, 100.0)
+        );
+        assert_eq!(
+            rotate_point(0.0, 0.0, Rotation::Rotate180, panel),
+            (100.0, 50.0)
+        );
+        assert_eq!(
+            rotate_point(0.0, 50.0, Rotation::Rotate270, panel),
+            (0.0, 0.0)
+        );
+        assert_eq!(
+            rotate_point(10.0, 20.0, Rotation::Rotate0, panel),
+            (10.0, 20.0)
+        );
+    }
+}
@@ -0,0 +1,49 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use crate::error::Result;
+
+use super::event::InputEvent;
+
+pub trait InputSource {
+    /// The next pending event, without blocking
+    fn poll_event(&mut self) -> Result<Option<InputEvent>>;
+
+    /// Block until an event arrives or `timeout` passes; `None` waits
+    /// forever
+    fn wait_event(&mut self, timeout: Option<Duration>) -> Result<Option<InputEvent>>;
+}
+
+/// An `InputSource` that replays queued events, for tests and demos
+#[derive(Debug, Clone, Default)]
+pub struct ScriptedInput {
+    events: VecDeque<InputEvent>,
+}
+
+impl ScriptedInput {
+    pub fn new(events: impl IntoIterator<Item = InputEvent>) -> Self {
+        Self {
+            events: events.into_iter().collect(),
+        }
+    }
+
+    pub fn push(&mut self, event: InputEvent) {
+        self.events.push_back(event);
+    }
+
+    /// True once every queued event has been delivered
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+}
+
+impl InputSource for ScriptedInput {
+    fn poll_event(&mut self) -> Result<Option<InputEvent>> {
+        Ok(self.events.pop_front())
+    }
+
+    /// Never blocks: a script has nothing more to wait for once it is empty
+    fn wait_event(&mut self, _timeout: Option<Duration>) -> Result<Option<InputEvent>> {
+        Ok(self.events.pop_front())
+    }
+}
@@ -1,10 +1,17 @@
 use taffy::prelude::*;
 
 use crate::style::{
-    Align as ProtonAlign, Dimension as ProtonDim, FlexDirection as ProtonDir,
-    Justify as ProtonJustify,
+    Align as ProtonAlign, Dimension as ProtonDim, Display as ProtonDisplay,
+    FlexDirection as ProtonDir, Justify as ProtonJustify,
 };
 
+pub fn convert_display(display: ProtonDisplay) -> Display {
+    match display {
+        ProtonDisplay::Flex => Display::Flex,
+        ProtonDisplay::None => Display::None,
+    }
+}
+
 pub fn convert_direction(dir: ProtonDir) -> taffy::FlexDirection {
     match dir {
         ProtonDir::Row => taffy::FlexDirection::Row,
@@ -1,18 +1,43 @@
+use std::collections::HashMap;
+use std::mem::{discriminant, Discriminant};
+
 use image::GenericImageView;
 use taffy::prelude::*;
 
+use crate::error::{Error, Result};
 use crate::font::Fonts;
-use crate::nodes::{ImageNode, ImageSource, ListViewNode, Node, ScrollViewNode, TextNode, ViewNode};
-use crate::style::{Dimension as ProtonDim, Rect, Size, TextWrap};
+use crate::nodes::{
+    ImageNode, ImageSource, ListViewNode, Node, ScrollViewNode, TextNode, ViewNode,
+};
+use crate::style::{
+    Dimension as ProtonDim, Display as ProtonDisplay, FlexDirection as ProtonDir, Rect,
+    ScrollAlign, Size, TextWrap,
+};
 use crate::text::{line_height, measure_text_width, wrap_text};
 
-use super::convert::{convert_align, convert_dimension, convert_direction, convert_justify};
+use super::convert::{
+    convert_align, convert_dimension, convert_direction, convert_display, convert_justify,
+};
 use super::node_data::NodeData;
+use super::scroll_metrics::ListPages;
 use super::tree::{LayoutNode, LayoutTree};
 
 pub struct LayoutEngine {
     taffy: TaffyTree<NodeData>,
     fonts: Fonts,
+    /// Taffy nodes of the previous frame, mirroring its `Node` tree
+    retained: Option<RetainedNode>,
+}
+
+/// A taffy node kept alive between frames so unchanged subtrees can reuse
+/// taffy's layout cache.
+struct RetainedNode {
+    id: NodeId,
+    kind: Discriminant<Node>,
+    key: Option<String>,
+    /// Source and intrinsic size, so images aren't decoded on every frame
+    image: Option<(ImageSource, (u32, u32))>,
+    children: Vec<RetainedNode>,
 }
 
 impl LayoutEngine {
@@ -20,239 +45,199 @@ impl LayoutEngine {
         Self {
             taffy: TaffyTree::new(),
             fonts: Fonts::new(),
+            retained: None,
         }
     }
 
-    pub fn compute(&mut self, root: &Node, available: Size) -> LayoutTree {
-        self.taffy.clear();
-
-        let root_id = self.build_taffy_node(root);
+    /// Lay out `root` within `available`.
+    ///
+    /// The taffy tree is kept between calls. The new `Node` tree is matched
+    /// against the previous one by key, or by position for unkeyed children,
+    /// and only nodes whose style or content changed are marked dirty.
+    pub fn compute(&mut self, root: &Node, available: Size) -> Result<LayoutTree> {
+        let previous = self.retained.take();
+        let retained = match self.sync_node(root, previous, false) {
+            Ok(retained) => retained,
+            Err(err) => {
+                // the taffy tree may be half updated, start over next frame
+                self.reset();
+                return Err(err);
+            }
+        };
+        let root_id = retained.id;
+        self.retained = Some(retained);
 
         let fonts = self.fonts.clone();
 
-        self.taffy
-            .compute_layout_with_measure(
-                root_id,
-                taffy::Size {
-                    width: AvailableSpace::Definite(available.width),
-                    height: AvailableSpace::Definite(available.height),
-                },
-                |known_dimensions, available_space, _node_id, node_context, _style| {
-                    measure_node(&fonts, known_dimensions, available_space, node_context)
-                },
-            )
-            .expect("Layout computation failed");
+        self.taffy.compute_layout_with_measure(
+            root_id,
+            taffy::Size {
+                width: AvailableSpace::Definite(available.width),
+                height: AvailableSpace::Definite(available.height),
+            },
+            |known_dimensions, available_space, _node_id, node_context, _style| {
+                measure_node(&fonts, known_dimensions, available_space, node_context)
+            },
+        )?;
 
         let mut nodes = Vec::new();
-        self.extract_layout(root_id, 0.0, 0.0, &mut nodes);
+        self.extract_layout(root, root_id, None, 0.0, 0.0, &mut nodes)?;
 
-        LayoutTree { nodes }
+        Ok(LayoutTree::new(nodes))
     }
 
-    fn build_taffy_node(&mut self, node: &Node) -> NodeId {
-        match node {
-            Node::View(view) => self.build_view_node(view),
-            Node::Text(text) => self.build_text_node(text),
-            Node::Image(img) => self.build_image_node(img),
-            Node::ScrollView(scroll) => self.build_scroll_view_node(scroll),
-            Node::ListView(list) => self.build_list_view_node(list),
-        }
+    /// Drop the retained taffy tree, forcing the next `compute` to rebuild
+    /// every node.
+    pub fn reset(&mut self) {
+        self.taffy.clear();
+        self.retained = None;
     }
 
-    fn build_view_node(&mut self, view: &ViewNode) -> NodeId {
-        let child_ids: Vec<NodeId> = view
-            .children
-            .iter()
-            .map(|child| self.build_taffy_node(child))
-            .collect();
-
-        let style = Style {
-            display: Display::Flex,
-            flex_direction: convert_direction(view.direction),
-            justify_content: Some(convert_justify(view.justify)),
-            align_items: Some(convert_align(view.align)),
-            padding: taffy::Rect {
-                left: LengthPercentage::Length(view.padding),
-                right: LengthPercentage::Length(view.padding),
-                top: LengthPercentage::Length(view.padding),
-                bottom: LengthPercentage::Length(view.padding),
-            },
-            gap: taffy::Size {
-                width: LengthPercentage::Length(view.gap),
-                height: LengthPercentage::Length(view.gap),
-            },
-            size: taffy::Size {
-                width: convert_dimension(view.width),
-                height: convert_dimension(view.height),
-            },
-            ..Default::default()
+    fn sync_node(
+        &mut self,
+        node: &Node,
+        previous: Option<RetainedNode>,
+        in_scroll_container: bool,
+    ) -> Result<RetainedNode> {
+        let mut previous = match previous {
+            Some(prev) if prev.kind == discriminant(node) && prev.key.as_deref() == node.key() => {
+                Some(prev)
+            }
+            Some(prev) => {
+                self.remove_subtree(prev)?;
+                None
+            }
+            None => None,
         };
 
-        self.taffy
-            .new_with_children(style, &child_ids)
-            .expect("Failed to create view node")
-    }
-
-    fn build_text_node(&mut self, text: &TextNode) -> NodeId {
-        let style = Style {
-            ..Default::default()
+        let mut image = None;
+        let mut style = match node {
+            Node::View(view) => view_style(view),
+            Node::Text(text) => text_style(text),
+            Node::Image(img) => {
+                let dimensions = match previous.as_mut().and_then(|prev| prev.image.take()) {
+                    Some((source, dimensions)) if source == img.source => dimensions,
+                    _ => get_image_dimensions(&img.source),
+                };
+                image = Some((img.source.clone(), dimensions));
+                image_style(img, dimensions)
+            }
+            Node::ScrollView(scroll) => scroll_view_style(scroll),
+            Node::ListView(list) => list_view_style(list),
         };
+        if in_scroll_container {
+            // content overflows a scroll container instead of shrinking to fit it
+            style.flex_shrink = 0.0;
+        }
 
-        self.taffy
-            .new_leaf_with_context(
-                style,
-                NodeData::Text {
-                    content: text.content.clone(),
-                    font_size: text.font_size,
-                    wrap: text.wrap,
-                    overflow: text.overflow,
-                    align: text.align,
-                    font: text.font,
-                },
-            )
-            .expect("Failed to create text node")
-    }
-
-    fn build_image_node(&mut self, img: &ImageNode) -> NodeId {
-        let (intrinsic_width, intrinsic_height) = get_image_dimensions(&img.source);
-
-        let width = match img.width {
-            ProtonDim::Auto => Dimension::Length(intrinsic_width as f32),
-            ProtonDim::Px(px) => Dimension::Length(px),
-            ProtonDim::Percent(p) => Dimension::Percent(p),
+        let context = match node {
+            Node::Text(text) => Some(NodeData::Text {
+                content: text.content.clone(),
+                font_size: text.font_size,
+                wrap: text.wrap,
+                overflow: text.overflow,
+                align: text.align,
+                font: text.font,
+            }),
+            Node::Image(img) => Some(NodeData::Image {
+                source: img.source.clone(),
+                fit: img.fit,
+            }),
+            _ => None,
         };
 
-        let height = match img.height {
-            ProtonDim::Auto => Dimension::Length(intrinsic_height as f32),
-            ProtonDim::Px(px) => Dimension::Length(px),
-            ProtonDim::Percent(p) => Dimension::Percent(p),
+        let (previous_id, previous_children) = match previous {
+            Some(prev) => (Some(prev.id), prev.children),
+            None => (None, Vec::new()),
         };
+        let previous_child_ids: Vec<NodeId> = previous_children.iter().map(|c| c.id).collect();
+
+        let scrolls = matches!(node, Node::ScrollView(_) | Node::ListView(_));
+        let children = self.sync_children(node.children(), previous_children, scrolls)?;
+        let child_ids: Vec<NodeId> = children.iter().map(|c| c.id).collect();
 
-        let style = Style {
-            size: taffy::Size { width, height },
-            ..Default::default()
+        let id = match previous_id {
+            Some(id) => {
+                if self.taffy.style(id)? != &style {
+                    self.taffy.set_style(id, style)?;
+                }
+                if self.taffy.get_node_context(id) != context.as_ref() {
+                    self.taffy.set_node_context(id, context)?;
+                }
+                if previous_child_ids != child_ids {
+                    self.taffy.set_children(id, &child_ids)?;
+                }
+                id
+            }
+            None => {
+                let id = self.taffy.new_with_children(style, &child_ids)?;
+                if context.is_some() {
+                    self.taffy.set_node_context(id, context)?;
+                }
+                id
+            }
         };
 
-        self.taffy
-            .new_leaf_with_context(
-                style,
-                NodeData::Image {
-                    source: img.source.clone(),
-                    fit: img.fit,
-                },
-            )
-            .expect("Failed to create image node")
+        Ok(RetainedNode {
+            id,
+            kind: discriminant(node),
+            key: node.key().map(str::to_string),
+            image,
+            children,
+        })
     }
 
-    fn build_scroll_view_node(&mut self, scroll: &ScrollViewNode) -> NodeId {
-        let child_ids: Vec<NodeId> = scroll
-            .children
+    /// Match children against the previous frame: keyed children by key,
+    /// unkeyed children by position. Unmatched previous children are removed.
+    fn sync_children(
+        &mut self,
+        children: &[Node],
+        previous: Vec<RetainedNode>,
+        in_scroll_container: bool,
+    ) -> Result<Vec<RetainedNode>> {
+        let by_key: HashMap<String, usize> = previous
             .iter()
-            .map(|child| self.build_taffy_node(child))
+            .enumerate()
+            .filter_map(|(i, prev)| prev.key.clone().map(|key| (key, i)))
             .collect();
+        let mut previous: Vec<Option<RetainedNode>> = previous.into_iter().map(Some).collect();
 
-        let style = Style {
-            display: Display::Flex,
-            flex_direction: convert_direction(scroll.direction),
-            justify_content: Some(convert_justify(scroll.justify)),
-            align_items: Some(convert_align(scroll.align)),
-            padding: taffy::Rect {
-                left: LengthPercentage::Length(scroll.padding),
-                right: LengthPercentage::Length(scroll.padding),
-                top: LengthPercentage::Length(scroll.padding),
-                bottom: LengthPercentage::Length(scroll.padding),
-            },
-            gap: taffy::Size {
-                width: LengthPercentage::Length(scroll.gap),
-                height: LengthPercentage::Length(scroll.gap),
-            },
-            size: taffy::Size {
-                width: convert_dimension(scroll.width),
-                height: convert_dimension(scroll.height),
-            },
-            overflow: taffy::Point {
-                x: taffy::Overflow::Visible,
-                y: taffy::Overflow::Scroll,
-            },
-            ..Default::default()
-        };
+        let mut synced = Vec::with_capacity(children.len());
+        for (i, child) in children.iter().enumerate() {
+            let slot = match child.key() {
+                Some(key) => by_key.get(key).copied(),
+                None => Some(i)
+                    .filter(|&i| matches!(previous.get(i), Some(Some(prev)) if prev.key.is_none())),
+            };
+            let matched = slot.and_then(|i| previous[i].take());
+            synced.push(self.sync_node(child, matched, in_scroll_container)?);
+        }
 
-        self.taffy
-            .new_with_children(style, &child_ids)
-            .map(|node_id| {
-                self.taffy
-                    .set_node_context(
-                        node_id,
-                        Some(NodeData::ScrollView {
-                            scroll_offset: scroll.scroll_offset,
-                            content_height: 0.0,
-                        }),
-                    )
-                    .expect("Failed to set scroll view context");
-                node_id
-            })
-            .expect("Failed to create scroll view node")
-    }
-
-    fn build_list_view_node(&mut self, list: &ListViewNode) -> NodeId {
-        let child_ids: Vec<NodeId> = list
-            .children
-            .iter()
-            .map(|child| self.build_taffy_node(child))
-            .collect();
+        for leftover in previous.into_iter().flatten() {
+            self.remove_subtree(leftover)?;
+        }
 
-        let style = Style {
-            display: Display::Flex,
-            flex_direction: convert_direction(list.direction),
-            justify_content: Some(convert_justify(list.justify)),
-            align_items: Some(convert_align(list.align)),
-            padding: taffy::Rect {
-                left: LengthPercentage::Length(list.padding),
-                right: LengthPercentage::Length(list.padding),
-                top: LengthPercentage::Length(list.padding),
-                bottom: LengthPercentage::Length(list.padding),
-            },
-            gap: taffy::Size {
-                width: LengthPercentage::Length(list.gap),
-                height: LengthPercentage::Length(list.gap),
-            },
-            size: taffy::Size {
-                width: convert_dimension(list.width),
-                height: convert_dimension(list.height),
-            },
-            overflow: taffy::Point {
-                x: taffy::Overflow::Visible,
-                y: taffy::Overflow::Scroll,
-            },
-            ..Default::default()
-        };
+        Ok(synced)
+    }
 
-        self.taffy
-            .new_with_children(style, &child_ids)
-            .map(|node_id| {
-                self.taffy
-                    .set_node_context(
-                        node_id,
-                        Some(NodeData::ListView {
-                            scroll_offset: list.scroll_offset,
-                            selected_index: list.selected_index,
-                            content_height: 0.0,
-                        }),
-                    )
-                    .expect("Failed to set list view context");
-                node_id
-            })
-            .expect("Failed to create list view node")
+    fn remove_subtree(&mut self, node: RetainedNode) -> Result<()> {
+        for child in node.children {
+            self.remove_subtree(child)?;
+        }
+        self.taffy.remove(node.id)?;
+        Ok(())
     }
 
     fn extract_layout(
         &self,
+        node: &Node,
         node_id: NodeId,
+        parent: Option<usize>,
         offset_x: f32,
         offset_y: f32,
         result: &mut Vec<LayoutNode>,
-    ) {
-        let layout = self.taffy.layout(node_id).expect("Node should have layout");
+    ) -> Result<usize> {
+        let layout = self.taffy.layout(node_id)?;
 
         let rect = Rect {
             x: offset_x + layout.location.x,
@@ -261,61 +246,290 @@ impl LayoutEngine {
             height: layout.size.height,
         };
 
-        let mut data = self.taffy.get_node_context(node_id).cloned();
-
-        if let Some(NodeData::ScrollView {
-            scroll_offset,
-            content_height: _,
-        }) = &data
-        {
-            let children = self.taffy.children(node_id).expect("Should get children");
-            let mut total_content_height = 0.0f32;
-
-            for child_id in &children {
-                let child_layout = self
-                    .taffy
-                    .layout(*child_id)
-                    .expect("Child should have layout");
-                let child_bottom = child_layout.location.y + child_layout.size.height;
-                total_content_height = total_content_height.max(child_bottom);
+        let data = match node {
+            Node::View(_) => Some(NodeData::View),
+            Node::ScrollView(scroll) => {
+                let (content_width, content_height) = self.content_size(node_id)?;
+                Some(NodeData::ScrollView {
+                    scroll_offset: clamp_scroll(scroll.scroll_offset, content_height, rect.height),
+                    scroll_offset_x: clamp_scroll(
+                        scroll.scroll_offset_x,
+                        content_width,
+                        rect.width,
+                    ),
+                    content_height,
+                    content_width,
+                })
+            }
+            Node::ListView(list) => {
+                let (content_width, content_height) = self.content_size(node_id)?;
+                let (extra_width, extra_height) = virtual_extent(list);
+                let (content_width, content_height) =
+                    (content_width + extra_width, content_height + extra_height);
+
+                let (mut scroll_offset_x, mut scroll_offset, pages) = if list.paged {
+                    if list.window.is_some() {
+                        return Err(Error::PagedVirtualList {
+                            index: result.len(),
+                        });
+                    }
+                    let (x, y, pages) = self.paged_list_offsets(list, node_id)?;
+                    (x, y, Some(pages))
+                } else {
+                    let (x, y) = self.list_scroll_offsets(list, node_id)?;
+                    (x, y, None)
+                };
+                // a page starts with a whole item, even on a short last page
+                if !(list.paged && list.direction == ProtonDir::Row) {
+                    scroll_offset_x = clamp_scroll(scroll_offset_x, content_width, rect.width);
+                }
+                if !(list.paged && list.direction == ProtonDir::Column) {
+                    scroll_offset = clamp_scroll(scroll_offset, content_height, rect.height);
+                }
+
+                Some(NodeData::ListView {
+                    scroll_offset,
+                    scroll_offset_x,
+                    selected_index: list.selected_index,
+                    content_height,
+                    content_width,
+                    first_item: list.first_item(),
+                    item_ids: list.item_ids.clone(),
+                    pages,
+                })
             }
+            Node::Text(_) | Node::Image(_) => self.taffy.get_node_context(node_id).cloned(),
+        };
+
+        let visible = node.is_painted() && parent.is_none_or(|p| result[p].visible);
+        let clips_children = match node {
+            Node::View(view) => view.clip_children,
+            Node::ScrollView(_) | Node::ListView(_) => true,
+            Node::Text(_) | Node::Image(_) => false,
+        };
 
-            data = Some(NodeData::ScrollView {
-                scroll_offset: *scroll_offset,
-                content_height: total_content_height,
-            });
+        let index = result.len();
+        result.push(LayoutNode {
+            rect,
+            data,
+            key: node.key().map(str::to_string),
+            focus_id: node.focus_id(),
+            focus_scope: node.focus_scope(),
+            visible,
+            clips_children,
+            parent,
+            children: Vec::new(),
+        });
+
+        // taffy lays out a virtualized list's children from its start, so
+        // move them past the space reserved for the items before them
+        let (shift_x, shift_y) = match node {
+            Node::ListView(list) => virtual_leading(list),
+            _ => (0.0, 0.0),
+        };
+        let child_ids = self.taffy.children(node_id)?;
+        let mut children = Vec::with_capacity(child_ids.len());
+        for (child, child_id) in node.children().iter().zip(child_ids) {
+            let child_index = self.extract_layout(
+                child,
+                child_id,
+                Some(index),
+                rect.x + shift_x,
+                rect.y + shift_y,
+                result,
+            )?;
+            children.push(child_index);
         }
+        result[index].children = children;
 
-        if let Some(NodeData::ListView {
-            scroll_offset,
-            selected_index,
-            content_height: _,
-        }) = &data
-        {
-            let children = self.taffy.children(node_id).expect("Should get children");
-            let mut total_content_height = 0.0f32;
-
-            for child_id in &children {
-                let child_layout = self
-                    .taffy
-                    .layout(*child_id)
-                    .expect("Child should have layout");
-                let child_bottom = child_layout.location.y + child_layout.size.height;
-                total_content_height = total_content_height.max(child_bottom);
+        Ok(index)
+    }
+
+    /// Unclamped `(x, y)` scroll offsets of a list, moved to bring the
+    /// selected child into view if `auto_scroll` is set
+    fn list_scroll_offsets(&self, list: &ListViewNode, node_id: NodeId) -> Result<(f32, f32)> {
+        let offsets = (list.scroll_offset_x, list.scroll_offset);
+        let Some(selected) = list.selected_index.filter(|_| list.auto_scroll) else {
+            return Ok(offsets);
+        };
+        let Some(extent) = self.item_extent(list, node_id, selected)? else {
+            return Ok(offsets);
+        };
+
+        let layout = self.taffy.layout(node_id)?;
+        Ok(match list.direction {
+            ProtonDir::Column => (
+                offsets.0,
+                align_scroll(
+                    list.scroll_align,
+                    offsets.1,
+                    extent,
+                    (
+                        layout.padding.top,
+                        layout.size.height - layout.padding.bottom,
+                    ),
+                ),
+            ),
+            ProtonDir::Row => (
+                align_scroll(
+                    list.scroll_align,
+                    offsets.0,
+                    extent,
+                    (
+                        layout.padding.left,
+                        layout.size.width - layout.padding.right,
+                    ),
+                ),
+                offsets.1,
+            ),
+        })
+    }
+
+    /// `(start, length)` of a list item along the main axis, from the edge
+    /// of the list. Items a virtualized list didn't build are estimated from
+    /// the space reserved for them.
+    fn item_extent(
+        &self,
+        list: &ListViewNode,
+        node_id: NodeId,
+        item: usize,
+    ) -> Result<Option<(f32, f32)>> {
+        let horizontal = list.direction == ProtonDir::Row;
+        let main = |location: taffy::Point<f32>, size: taffy::Size<f32>| {
+            if horizontal {
+                (location.x, size.width)
+            } else {
+                (location.y, size.height)
             }
+        };
+        let padding = self.taffy.layout(node_id)?.padding;
+        let padding_start = if horizontal {
+            padding.left
+        } else {
+            padding.top
+        };
+        let leading = list.window.map_or(0.0, |window| window.leading);
+        let first = list.first_item();
+        let built = first..first + list.children.len();
+
+        if built.contains(&item) {
+            let position = item - first;
+            if list.children[position].display() == ProtonDisplay::None {
+                return Ok(None);
+            }
+            let child = self
+                .taffy
+                .layout(self.taffy.child_at_index(node_id, position)?)?;
+            let (start, length) = main(child.location, child.size);
+            return Ok(Some((start + leading, length)));
+        }
+
+        let Some(window) = list.window.filter(|window| item < window.item_count) else {
+            return Ok(None);
+        };
+        // each unbuilt item is assumed to take an equal share of the space
+        // reserved on its side, including one gap
+        let extent = if item < first {
+            let share = window.leading / first as f32;
+            (padding_start + item as f32 * share, share - list.gap)
+        } else {
+            // where the first item after the built ones starts
+            let trailing_start = match self.taffy.children(node_id)?.last() {
+                Some(&last) => {
+                    let child = self.taffy.layout(last)?;
+                    let (start, length) = main(child.location, child.size);
+                    start + length + leading + list.gap
+                }
+                None => padding_start + leading,
+            };
+            let share = window.trailing / (window.item_count - built.end) as f32;
+            (
+                trailing_start + (item - built.end) as f32 * share,
+                share - list.gap,
+            )
+        };
+        Ok(Some((extent.0, extent.1.max(0.0))))
+    }
+
+    /// Offsets of a list in paged mode, with the current page's start along
+    /// the main axis, and its pages. A page ends before the first item that
+    /// doesn't fit in the viewport entirely, which then starts the next one.
+    /// Every item has to be built, so the list can't be virtualized.
+    fn paged_list_offsets(
+        &self,
+        list: &ListViewNode,
+        node_id: NodeId,
+    ) -> Result<(f32, f32, ListPages)> {
+        let layout = self.taffy.layout(node_id)?;
+        let horizontal = list.direction == ProtonDir::Row;
+        let (size, padding_start, padding_end) = if horizontal {
+            (layout.size.width, layout.padding.left, layout.padding.right)
+        } else {
+            (
+                layout.size.height,
+                layout.padding.top,
+                layout.padding.bottom,
+            )
+        };
 
-            data = Some(NodeData::ListView {
-                scroll_offset: *scroll_offset,
-                selected_index: *selected_index,
-                content_height: total_content_height,
-            });
+        let mut starts = vec![0.0f32];
+        let mut selected_page = None;
+        for (position, child_id) in self.taffy.children(node_id)?.into_iter().enumerate() {
+            let child = self.taffy.layout(child_id)?;
+            let (start, length) = if horizontal {
+                (child.location.x, child.size.width)
+            } else {
+                (child.location.y, child.size.height)
+            };
+
+            let page_start = starts[starts.len() - 1];
+            if start + length > page_start + size - padding_end
+                && start > page_start + padding_start
+            {
+                starts.push(start - padding_start);
+            }
+            if list.selected_index == Some(position) {
+                selected_page = Some(starts.len() - 1);
+            }
         }
 
-        result.push(LayoutNode { rect, data });
+        let current = match selected_page {
+            Some(page) if list.auto_scroll => page,
+            _ => list.page.min(starts.len() - 1),
+        };
+        let pages = ListPages {
+            current,
+            count: starts.len(),
+        };
+        let offset = starts[current];
+
+        Ok(if horizontal {
+            (offset, list.scroll_offset, pages)
+        } else {
+            (list.scroll_offset_x, offset, pages)
+        })
+    }
+
+    /// Width and height spanned by the children of a scroll container,
+    /// including its padding
+    fn content_size(&self, node_id: NodeId) -> Result<(f32, f32)> {
+        let padding = self.taffy.layout(node_id)?.padding;
+        let mut total_content_width = 0.0f32;
+        let mut total_content_height = 0.0f32;
 
-        for child_id in self.taffy.children(node_id).expect("Should get children") {
-            self.extract_layout(child_id, rect.x, rect.y, result);
+        for child_id in self.taffy.children(node_id)? {
+            let child_layout = self.taffy.layout(child_id)?;
+            let child_right = child_layout.location.x + child_layout.size.width;
+            let child_bottom = child_layout.location.y + child_layout.size.height;
+            total_content_width = total_content_width.max(child_right);
+            total_content_height = total_content_height.max(child_bottom);
         }
+
+        // the trailing padding scrolls into view after the last child
+        Ok((
+            total_content_width + padding.right,
+            total_content_height + padding.bottom,
+        ))
     }
 }
 
@@ -325,6 +539,167 @@ impl Default for LayoutEngine {
     }
 }
 
+fn view_style(view: &ViewNode) -> Style {
+    Style {
+        display: convert_display(view.display),
+        flex_direction: convert_direction(view.direction),
+        justify_content: Some(convert_justify(view.justify)),
+        align_items: Some(convert_align(view.align)),
+        padding: taffy::Rect {
+            left: LengthPercentage::Length(view.padding),
+            right: LengthPercentage::Length(view.padding),
+            top: LengthPercentage::Length(view.padding),
+            bottom: LengthPercentage::Length(view.padding),
+        },
+        gap: taffy::Size {
+            width: LengthPercentage::Length(view.gap),
+            height: LengthPercentage::Length(view.gap),
+        },
+        size: taffy::Size {
+            width: convert_dimension(view.width),
+            height: convert_dimension(view.height),
+        },
+        ..Default::default()
+    }
+}
+
+fn text_style(text: &TextNode) -> Style {
+    Style {
+        display: convert_display(text.display),
+        ..Default::default()
+    }
+}
+
+fn image_style(img: &ImageNode, (intrinsic_width, intrinsic_height): (u32, u32)) -> Style {
+    let width = match img.width {
+        ProtonDim::Auto => Dimension::Length(intrinsic_width as f32),
+        ProtonDim::Px(px) => Dimension::Length(px),
+        ProtonDim::Percent(p) => Dimension::Percent(p),
+    };
+
+    let height = match img.height {
+        ProtonDim::Auto => Dimension::Length(intrinsic_height as f32),
+        ProtonDim::Px(px) => Dimension::Length(px),
+        ProtonDim::Percent(p) => Dimension::Percent(p),
+    };
+
+    Style {
+        display: convert_display(img.display),
+        size: taffy::Size { width, height },
+        ..Default::default()
+    }
+}
+
+fn scroll_view_style(scroll: &ScrollViewNode) -> Style {
+    Style {
+        display: convert_display(scroll.display),
+        flex_direction: convert_direction(scroll.direction),
+        justify_content: Some(convert_justify(scroll.justify)),
+        align_items: Some(convert_align(scroll.align)),
+        padding: taffy::Rect {
+            left: LengthPercentage::Length(scroll.padding),
+            right: LengthPercentage::Length(scroll.padding),
+            top: LengthPercentage::Length(scroll.padding),
+            bottom: LengthPercentage::Length(scroll.padding),
+        },
+        gap: taffy::Size {
+            width: LengthPercentage::Length(scroll.gap),
+            height: LengthPercentage::Length(scroll.gap),
+        },
+        size: taffy::Size {
+            width: convert_dimension(scroll.width),
+            height: convert_dimension(scroll.height),
+        },
+        overflow: scroll_overflow(scroll.direction),
+        ..Default::default()
+    }
+}
+
+fn list_view_style(list: &ListViewNode) -> Style {
+    Style {
+        display: convert_display(list.display),
+        flex_direction: convert_direction(list.direction),
+        justify_content: Some(convert_justify(list.justify)),
+        align_items: Some(convert_align(list.align)),
+        padding: taffy::Rect {
+            left: LengthPercentage::Length(list.padding),
+            right: LengthPercentage::Length(list.padding),
+            top: LengthPercentage::Length(list.padding),
+            bottom: LengthPercentage::Length(list.padding),
+        },
+        gap: taffy::Size {
+            width: LengthPercentage::Length(list.gap),
+            height: LengthPercentage::Length(list.gap),
+        },
+        size: taffy::Size {
+            width: convert_dimension(list.width),
+            height: convert_dimension(list.height),
+        },
+        overflow: scroll_overflow(list.direction),
+        ..Default::default()
+    }
+}
+
+/// Space reserved before the first child of a virtualized list, as `(x, y)`
+fn virtual_leading(list: &ListViewNode) -> (f32, f32) {
+    let leading = list.window.map_or(0.0, |window| window.leading);
+    along_main_axis(list.direction, leading)
+}
+
+/// Space reserved for all the items a virtualized list didn't build
+fn virtual_extent(list: &ListViewNode) -> (f32, f32) {
+    let extent = list
+        .window
+        .map_or(0.0, |window| window.leading + window.trailing);
+    along_main_axis(list.direction, extent)
+}
+
+fn along_main_axis(direction: ProtonDir, length: f32) -> (f32, f32) {
+    match direction {
+        ProtonDir::Row => (length, 0.0),
+        ProtonDir::Column => (0.0, length),
+    }
+}
+
+/// Keep a scroll offset between the start and end of the content
+fn clamp_scroll(offset: f32, content: f32, viewport: f32) -> f32 {
+    offset.min(content - viewport).max(0.0)
+}
+
+/// Offset that places `(start, length)` of the content within the
+/// `(start, end)` of the viewport, both measured from the container's edge
+pub(crate) fn align_scroll(
+    align: ScrollAlign,
+    offset: f32,
+    (start, length): (f32, f32),
+    (view_start, view_end): (f32, f32),
+) -> f32 {
+    let align_start = start - view_start;
+    let align_end = start + length - view_end;
+    match align {
+        ScrollAlign::Start => align_start,
+        ScrollAlign::End => align_end,
+        ScrollAlign::Center => start + length / 2.0 - (view_start + view_end) / 2.0,
+        // a child taller than the viewport shows its start
+        ScrollAlign::Nearest if offset > align_start || align_end > align_start => align_start,
+        ScrollAlign::Nearest => offset.max(align_end),
+    }
+}
+
+/// Scroll containers scroll along their main axis
+fn scroll_overflow(direction: ProtonDir) -> taffy::Point<taffy::Overflow> {
+    match direction {
+        ProtonDir::Row => taffy::Point {
+            x: taffy::Overflow::Scroll,
+            y: taffy::Overflow::Visible,
+        },
+        ProtonDir::Column => taffy::Point {
+            x: taffy::Overflow::Visible,
+            y: taffy::Overflow::Scroll,
+        },
+    }
+}
+
 fn measure_node(
     fonts: &Fonts,
     known_dimensions: taffy::Size<Option<f32>>,
@@ -421,3 +796,277 @@ fn get_image_dimensions(source: &ImageSource) -> (u32, u32) {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout::ScrollMetrics;
+    use crate::nodes::{ListItem, ListView, ScrollView, Text, View, VirtualList};
+    use crate::test_util::block;
+
+    #[test]
+    fn test_hidden_node_takes_no_space() {
+        let mut engine = LayoutEngine::new();
+        let root = View::column()
+            .child(block(50.0))
+            .child(block(50.0).hidden())
+            .child(block(50.0))
+            .build();
+
+        let layout = engine.compute(&root, Size::new(400.0, 400.0)).unwrap();
+
+        assert_eq!(layout.nodes.len(), 4);
+        assert_eq!(layout.nodes[2].rect.height, 0.0);
+        assert_eq!(layout.nodes[3].rect.y, 50.0);
+    }
+
+    #[test]
+    fn test_invisible_node_keeps_its_space() {
+        let mut engine = LayoutEngine::new();
+        let root = View::column()
+            .child(block(50.0))
+            .child(block(50.0).invisible())
+            .child(block(50.0))
+            .build();
+
+        let layout = engine.compute(&root, Size::new(400.0, 400.0)).unwrap();
+
+        assert_eq!(layout.nodes.len(), 4);
+        assert_eq!(layout.nodes[2].rect.height, 50.0);
+        assert_eq!(layout.nodes[3].rect.y, 100.0);
+    }
+
+    #[test]
+    fn test_lookup_by_key() {
+        let mut engine = LayoutEngine::new();
+        let root = View::column()
+            .key("root")
+            .child(block(50.0))
+            .child(View::row().key("row").child(block(30.0).key("button")))
+            .build();
+
+        let layout = engine.compute(&root, Size::new(400.0, 400.0)).unwrap();
+
+        assert_eq!(layout.index_of("button"), Some(3));
+        assert_eq!(
+            layout.rect_of("button"),
+            Some(Rect::new(0.0, 50.0, 100.0, 30.0))
+        );
+        assert_eq!(layout.parent(3), layout.index_of("row"));
+        assert_eq!(layout.children(0), &[1, 2]);
+        assert_eq!(layout.path_to(3), vec![0, 2, 3]);
+        assert_eq!(layout.rect_of("missing"), None);
+    }
+
+    #[test]
+    fn test_retained_tree_is_reused() {
+        let mut engine = LayoutEngine::new();
+        let build = |offset: f32, height: f32| {
+            View::column()
+                .child(block(height))
+                .child(
+                    ScrollView::vertical()
+                        .height(ProtonDim::Px(100.0))
+                        .scroll_offset(offset)
+                        .child(block(300.0)),
+                )
+                .build()
+        };
+
+        engine
+            .compute(&build(0.0, 50.0), Size::new(400.0, 400.0))
+            .unwrap();
+        let node_count = engine.taffy.total_node_count();
+
+        let layout = engine
+            .compute(&build(20.0, 50.0), Size::new(400.0, 400.0))
+            .unwrap();
+        assert_eq!(engine.taffy.total_node_count(), node_count);
+        assert!(matches!(
+            layout.nodes[2].data,
+            Some(NodeData::ScrollView { scroll_offset, .. }) if scroll_offset == 20.0
+        ));
+
+        let layout = engine
+            .compute(&build(20.0, 80.0), Size::new(400.0, 400.0))
+            .unwrap();
+        assert_eq!(engine.taffy.total_node_count(), node_count);
+        assert_eq!(layout.nodes[2].rect.y, 80.0);
+    }
+
+    #[test]
+    fn test_retained_tree_matches_children_by_key() {
+        let mut engine = LayoutEngine::new();
+        let build = |keys: &[&str]| {
+            View::column()
+                .children(keys.iter().map(|key| block(50.0).key(*key)))
+                .build()
+        };
+
+        engine
+            .compute(&build(&["a", "b", "c"]), Size::new(400.0, 400.0))
+            .unwrap();
+        let layout = engine
+            .compute(&build(&["a", "c"]), Size::new(400.0, 400.0))
+            .unwrap();
+
+        assert_eq!(engine.taffy.total_node_count(), 3);
+        assert_eq!(layout.rect_of("c").map(|r| r.y), Some(50.0));
+
+        let layout = engine
+            .compute(&build(&["c", "b", "a"]), Size::new(400.0, 400.0))
+            .unwrap();
+        assert_eq!(engine.taffy.total_node_count(), 4);
+        assert_eq!(layout.rect_of("a").map(|r| r.y), Some(100.0));
+    }
+
+    #[test]
+    fn test_node_kind_change_rebuilds_subtree() {
+        let mut engine = LayoutEngine::new();
+        engine
+            .compute(
+                &View::column().child(block(50.0).child(block(10.0))).build(),
+                Size::new(400.0, 400.0),
+            )
+            .unwrap();
+        let layout = engine
+            .compute(
+                &View::column().child(Text::new("hello")).build(),
+                Size::new(400.0, 400.0),
+            )
+            .unwrap();
+
+        assert_eq!(engine.taffy.total_node_count(), 2);
+        assert!(matches!(layout.nodes[1].data, Some(NodeData::Text { .. })));
+    }
+
+    #[test]
+    fn test_validate_detects_mismatched_tree() {
+        let mut engine = LayoutEngine::new();
+        let root = View::column().child(block(50.0)).build();
+        let layout = engine.compute(&root, Size::new(400.0, 400.0)).unwrap();
+
+        assert!(layout.validate(&root).is_ok());
+        assert!(matches!(
+            layout.validate(&View::column().child(Text::new("a")).build()),
+            Err(Error::LayoutKindMismatch { index: 1, .. })
+        ));
+        assert!(matches!(
+            layout.validate(&View::column().build()),
+            Err(Error::LayoutChildCountMismatch { index: 0, .. })
+        ));
+    }
+
+    #[test]
+    fn test_horizontal_scroll_content_width() {
+        let mut engine = LayoutEngine::new();
+        let root = ScrollView::horizontal()
+            .width(ProtonDim::Px(150.0))
+            .height(ProtonDim::Px(50.0))
+            .children((0..4).map(|_| block(50.0)))
+            .build();
+
+        let layout = engine.compute(&root, Size::new(400.0, 400.0)).unwrap();
+
+        assert_eq!(layout.nodes[0].rect.width, 150.0);
+        assert_eq!(layout.nodes[4].rect.x, 300.0);
+        assert!(matches!(
+            layout.nodes[0].data,
+            Some(NodeData::ScrollView { content_width, .. }) if content_width == 400.0
+        ));
+    }
+
+    fn selected_list_offset(selected: usize, scroll_offset: f32, align: ScrollAlign) -> f32 {
+        let root = ListView::vertical()
+            .height(ProtonDim::Px(100.0))
+            .selected_index(Some(selected))
+            .scroll_offset(scroll_offset)
+            .scroll_align(align)
+            .children((0..10).map(|_| block(40.0)))
+            .build();
+        let layout = LayoutEngine::new()
+            .compute(&root, Size::new(400.0, 400.0))
+            .unwrap();
+        layout.scroll_metrics(0).unwrap().offset_y
+    }
+
+    #[test]
+    fn test_list_auto_scrolls_to_nearest() {
+        // item 5 spans 200..240
+        assert_eq!(selected_list_offset(5, 0.0, ScrollAlign::Nearest), 140.0);
+        assert_eq!(selected_list_offset(5, 300.0, ScrollAlign::Nearest), 200.0);
+        assert_eq!(selected_list_offset(5, 160.0, ScrollAlign::Nearest), 160.0);
+    }
+
+    #[test]
+    fn test_list_auto_scroll_alignment() {
+        assert_eq!(selected_list_offset(5, 0.0, ScrollAlign::Start), 200.0);
+        assert_eq!(selected_list_offset(5, 0.0, ScrollAlign::Center), 170.0);
+        assert_eq!(selected_list_offset(5, 0.0, ScrollAlign::End), 140.0);
+        // clamped to the content
+        assert_eq!(selected_list_offset(9, 0.0, ScrollAlign::Start), 300.0);
+        assert_eq!(selected_list_offset(0, 50.0, ScrollAlign::Center), 0.0);
+    }
+
+    #[test]
+    fn test_list_auto_scroll_disabled() {
+        let root = ListView::vertical()
+            .height(ProtonDim::Px(100.0))
+            .selected_index(Some(5))
+            .auto_scroll(false)
+            .children((0..10).map(|_| block(40.0)))
+            .build();
+        let layout = LayoutEngine::new()
+            .compute(&root, Size::new(400.0, 400.0))
+            .unwrap();
+
+        assert_eq!(layout.scroll_metrics(0).unwrap().offset_y, 0.0);
+    }
+
+    fn paged_list(page: usize, selected: Option<usize>) -> ScrollMetrics {
+        let root = ListView::vertical()
+            .height(ProtonDim::Px(100.0))
+            .paged(true)
+            .page(page)
+            .selected_index(selected)
+            .children((0..10).map(|_| block(40.0)))
+            .build();
+        let layout = LayoutEngine::new()
+            .compute(&root, Size::new(400.0, 400.0))
+            .unwrap();
+        layout.scroll_metrics(0).unwrap()
+    }
+
+    #[test]
+    fn test_paged_list_keeps_items_whole() {
+        // two 40px items fit on each 100px page
+        let metrics = paged_list(1, None);
+        assert_eq!(metrics.page_count(), 5);
+        assert_eq!(metrics.current_page(), 1);
+        assert_eq!(metrics.offset_y, 80.0);
+
+        // the last page isn't clamped back onto the previous items
+        assert_eq!(paged_list(4, None).offset_y, 320.0);
+        assert_eq!(paged_list(9, None).current_page(), 4);
+    }
+
+    #[test]
+    fn test_paged_list_follows_selection() {
+        let pages = paged_list(0, Some(5)).pages.unwrap();
+        assert_eq!(pages.current, 2);
+        assert_eq!(pages.next(), 3);
+        assert_eq!(pages.prev(), 1);
+    }
+
+    #[test]
+    fn test_paged_list_cannot_be_virtualized() {
+        let list = ListView::vertical()
+            .height(ProtonDim::Px(100.0))
+            .paged(true);
+        let root = VirtualList::new(100, 40.0)
+            .build(list, |i| ListItem::new(i as u32, block(40.0)))
+            .build();
+        let result = LayoutEngine::new().compute(&root, Size::new(400.0, 400.0));
+        assert!(matches!(result, Err(Error::PagedVirtualList { index: 0 })));
+    }
+}
@@ -0,0 +1,279 @@
+use crate::style::Rect;
+
+use super::node_data::NodeData;
+use super::tree::LayoutTree;
+
+/// Result of `LayoutTree::hit_test`
+#[derive(Debug, Clone, PartialEq)]
+pub struct HitTestResult<'a> {
+    /// Index of the topmost node under the point
+    pub index: usize,
+    /// Indices from the root down to `index`, inclusive
+    pub path: Vec<usize>,
+    /// Key of the deepest keyed node on `path`
+    pub key: Option<&'a str>,
+    /// Innermost `ListView` on `path` and the item that was hit
+    pub list_item: Option<ListItemHit>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ListItemHit {
+    /// Index of the `ListView` node in `LayoutTree::nodes`
+    pub list: usize,
+    /// Item index of the hit child, counted from the start of the whole
+    /// list for virtualized lists
+    pub item: usize,
+    /// Id of the hit child, if it was added as a `ListItem`
+    pub id: Option<u32>,
+}
+
+impl LayoutTree {
+    /// Find the topmost painted node under a point in screen coordinates.
+    ///
+    /// Scroll offsets of `ScrollView`/`ListView` ancestors are applied, and
+    /// their rects and those of `clip_children` views clip what can be hit,
+    /// matching what the renderer draws.
+    pub fn hit_test(&self, x: f32, y: f32) -> Option<HitTestResult<'_>> {
+        if self.nodes.is_empty() {
+            return None;
+        }
+
+        let mut path = Vec::new();
+        if !self.hit_node(0, x, y, (0.0, 0.0), None, &mut path) {
+            return None;
+        }
+        path.reverse();
+
+        let index = *path.last()?;
+        let key = path
+            .iter()
+            .rev()
+            .find_map(|&i| self.nodes[i].key.as_deref());
+
+        let list_item = path.windows(2).rev().find_map(|pair| {
+            let (list, child) = (pair[0], pair[1]);
+            match &self.nodes[list].data {
+                Some(NodeData::ListView {
+                    first_item,
+                    item_ids,
+                    ..
+                }) => self.nodes[list]
+                    .children
+                    .iter()
+                    .position(|&c| c == child)
+                    .map(|position| ListItemHit {
+                        list,
+                        item: first_item + position,
+                        id: item_ids.get(position).copied().flatten(),
+                    }),
+                _ => None,
+            }
+        });
+
+        Some(HitTestResult {
+            index,
+            path,
+            key,
+            list_item,
+        })
+    }
+
+    /// Pushes the hit path in reverse (deepest node first) and returns
+    /// whether anything in this subtree was hit.
+    fn hit_node(
+        &self,
+        index: usize,
+        x: f32,
+        y: f32,
+        scroll: (f32, f32),
+        clip: Option<Rect>,
+        path: &mut Vec<usize>,
+    ) -> bool {
+        let node = &self.nodes[index];
+        if !node.visible {
+            return false;
+        }
+
+        let rect = Rect {
+            x: node.rect.x - scroll.0,
+            y: node.rect.y - scroll.1,
+            ..node.rect
+        };
+
+        let child_scroll = match &node.data {
+            Some(NodeData::ScrollView {
+                scroll_offset,
+                scroll_offset_x,
+                ..
+            })
+            | Some(NodeData::ListView {
+                scroll_offset,
+                scroll_offset_x,
+                ..
+            }) => (scroll.0 + scroll_offset_x, scroll.1 + scroll_offset),
+            _ => scroll,
+        };
+
+        let child_clip = if node.clips_children {
+            match clip {
+                Some(clip) => match clip.intersect(&rect) {
+                    Some(child_clip) => Some(child_clip),
+                    // nothing inside a fully clipped node can be hit
+                    None => return self.hit_self(index, &rect, x, y, Some(clip), path),
+                },
+                None => Some(rect),
+            }
+        } else {
+            clip
+        };
+
+        // later children are painted on top
+        for &child in node.children.iter().rev() {
+            if self.hit_node(child, x, y, child_scroll, child_clip, path) {
+                path.push(index);
+                return true;
+            }
+        }
+
+        self.hit_self(index, &rect, x, y, clip, path)
+    }
+
+    fn hit_self(
+        &self,
+        index: usize,
+        rect: &Rect,
+        x: f32,
+        y: f32,
+        clip: Option<Rect>,
+        path: &mut Vec<usize>,
+    ) -> bool {
+        let in_clip = clip.is_none_or(|clip| clip.contains(x, y));
+        if in_clip && rect.contains(x, y) {
+            path.push(index);
+            true
+        } else {
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::layout::LayoutEngine;
+    use crate::nodes::{ListView, ScrollView, Text, View};
+    use crate::style::{Dimension, Size};
+    use crate::test_util::block;
+
+    #[test]
+    fn test_hit_topmost_keyed_node() {
+        let mut engine = LayoutEngine::new();
+        let root = View::column()
+            .width(Dimension::Percent(1.0))
+            .child(block(50.0).key("a"))
+            .child(block(50.0).key("b").child(block(20.0)))
+            .build();
+        let layout = engine.compute(&root, Size::new(200.0, 200.0)).unwrap();
+
+        let hit = layout.hit_test(10.0, 60.0).unwrap();
+        assert_eq!(hit.path, vec![0, 2, 3]);
+        assert_eq!(hit.key, Some("b"));
+
+        assert_eq!(layout.hit_test(10.0, 10.0).unwrap().key, Some("a"));
+        assert!(layout.hit_test(150.0, 10.0).unwrap().key.is_none());
+    }
+
+    #[test]
+    fn test_hit_skips_invisible_nodes() {
+        let mut engine = LayoutEngine::new();
+        let root = View::column()
+            .width(Dimension::Percent(1.0))
+            .child(block(50.0).key("a").invisible())
+            .build();
+        let layout = engine.compute(&root, Size::new(200.0, 200.0)).unwrap();
+
+        assert_eq!(layout.hit_test(10.0, 10.0).unwrap().index, 0);
+    }
+
+    #[test]
+    fn test_hit_list_item_with_scroll_offset() {
+        let mut engine = LayoutEngine::new();
+        let root = View::column()
+            .width(Dimension::Percent(1.0))
+            .child(
+                ListView::vertical()
+                    .height(Dimension::Px(100.0))
+                    .scroll_offset(120.0)
+                    .children((0..10).map(|i| Text::new(format!("Item {}", i)))),
+            )
+            .child(block(50.0).key("below"))
+            .build();
+        let layout = engine.compute(&root, Size::new(200.0, 400.0)).unwrap();
+
+        let expected = layout
+            .children(1)
+            .iter()
+            .position(|&i| layout.nodes[i].rect.contains(10.0, 160.0));
+        let hit = layout.hit_test(10.0, 40.0).unwrap();
+        assert_eq!(hit.list_item.map(|l| l.item), expected);
+
+        // items scrolled past the list's bottom edge are clipped
+        let hit = layout.hit_test(10.0, 120.0).unwrap();
+        assert_eq!(hit.key, Some("below"));
+        assert!(hit.list_item.is_none());
+    }
+
+    #[test]
+    fn test_hit_clipped_by_scroll_view() {
+        let mut engine = LayoutEngine::new();
+        let root = View::column()
+            .width(Dimension::Percent(1.0))
+            .height(Dimension::Percent(1.0))
+            .child(
+                ScrollView::vertical()
+                    .key("scroll")
+                    .height(Dimension::Px(100.0))
+                    .child(block(300.0).key("tall")),
+            )
+            .build();
+        let layout = engine.compute(&root, Size::new(200.0, 400.0)).unwrap();
+
+        assert_eq!(layout.hit_test(10.0, 50.0).unwrap().key, Some("tall"));
+        assert!(layout.hit_test(10.0, 150.0).unwrap().key.is_none());
+    }
+
+    #[test]
+    fn test_hit_horizontal_list_item() {
+        let mut engine = LayoutEngine::new();
+        let root = ListView::horizontal()
+            .width(Dimension::Px(150.0))
+            .height(Dimension::Px(50.0))
+            .scroll_offset_x(150.0)
+            .children((0..4).map(|_| block(50.0)))
+            .build();
+        let layout = engine.compute(&root, Size::new(400.0, 400.0)).unwrap();
+
+        // item 1 spans 100..200 in content, so -50..50 on screen
+        let hit = layout.hit_test(10.0, 10.0).unwrap();
+        assert_eq!(hit.list_item.map(|l| l.item), Some(1));
+        assert!(layout.hit_test(160.0, 10.0).is_none());
+    }
+
+    #[test]
+    fn test_hit_clipped_by_view() {
+        let mut engine = LayoutEngine::new();
+        let root = View::column()
+            .width(Dimension::Percent(1.0))
+            .child(
+                View::row()
+                    .height(Dimension::Px(50.0))
+                    .key("card")
+                    .clip_children(true)
+                    .child(block(100.0).key("inner")),
+            )
+            .build();
+        let layout = engine.compute(&root, Size::new(200.0, 200.0)).unwrap();
+
+        assert_eq!(layout.hit_test(10.0, 40.0).unwrap().key, Some("inner"));
+        assert!(layout.hit_test(10.0, 60.0).is_none());
+    }
+}
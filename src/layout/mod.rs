@@ -1,8 +1,13 @@
 mod convert;
 mod engine;
+mod hit_test;
 mod node_data;
+mod scroll_metrics;
 mod tree;
 
+pub(crate) use engine::align_scroll;
 pub use engine::LayoutEngine;
+pub use hit_test::{HitTestResult, ListItemHit};
 pub use node_data::NodeData;
+pub use scroll_metrics::{ListPages, ScrollMetrics};
 pub use tree::{LayoutNode, LayoutTree};
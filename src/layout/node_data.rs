@@ -1,7 +1,9 @@
 use crate::nodes::ImageSource;
 use crate::style::{ImageFit, TextAlign, TextFont, TextOverflow, TextWrap};
 
-#[derive(Debug, Clone)]
+use super::scroll_metrics::ListPages;
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum NodeData {
     View,
     Text {
@@ -16,14 +18,37 @@ pub enum NodeData {
         source: ImageSource,
         fit: ImageFit,
     },
+    // scroll offsets are clamped to the content
+    // content size includes the container's padding
     ScrollView {
         scroll_offset: f32,
+        scroll_offset_x: f32,
         content_height: f32,
+        content_width: f32,
     },
+    // first_item is the item index of the first child of a virtualized list
+    // in paged mode the main-axis offset is the current page's start, which
+    // can be past the end of the content on the last page
     ListView {
         scroll_offset: f32,
+        scroll_offset_x: f32,
         selected_index: Option<usize>,
         content_height: f32,
+        content_width: f32,
+        first_item: usize,
+        item_ids: Vec<Option<u32>>,
+        pages: Option<ListPages>,
     },
 }
 
+impl NodeData {
+    pub(crate) fn kind_name(&self) -> &'static str {
+        match self {
+            NodeData::View => "View",
+            NodeData::Text { .. } => "Text",
+            NodeData::Image { .. } => "Image",
+            NodeData::ScrollView { .. } => "ScrollView",
+            NodeData::ListView { .. } => "ListView",
+        }
+    }
+}
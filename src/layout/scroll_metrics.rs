@@ -0,0 +1,160 @@
+use crate::style::Size;
+
+use super::node_data::NodeData;
+use super::tree::LayoutTree;
+
+/// Geometry of a `ScrollView` or `ListView` after layout
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ScrollMetrics {
+    /// Visible size of the container
+    pub viewport: Size,
+    /// Size of the scrollable content, including the container's padding
+    pub content: Size,
+    /// Horizontal offset used for this layout, clamped to the content except
+    /// along a paged list's main axis, where it is the current page's start
+    pub offset_x: f32,
+    /// Vertical offset used for this layout, clamped to the content except
+    /// along a paged list's main axis, where it is the current page's start
+    pub offset_y: f32,
+    /// Pages of a `ListView` in paged mode
+    pub pages: Option<ListPages>,
+}
+
+/// Pages of a paged `ListView`, each starting with a whole item
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ListPages {
+    /// Zero-based page shown by this layout
+    pub current: usize,
+    pub count: usize,
+}
+
+impl ListPages {
+    pub fn is_first(&self) -> bool {
+        self.current == 0
+    }
+
+    pub fn is_last(&self) -> bool {
+        self.current + 1 >= self.count
+    }
+
+    /// Page to show after a page-forward button press
+    pub fn next(&self) -> usize {
+        (self.current + 1).min(self.count.saturating_sub(1))
+    }
+
+    /// Page to show after a page-back button press
+    pub fn prev(&self) -> usize {
+        self.current.saturating_sub(1)
+    }
+}
+
+impl ScrollMetrics {
+    pub fn max_offset_x(&self) -> f32 {
+        (self.content.width - self.viewport.width).max(0.0)
+    }
+
+    pub fn max_offset_y(&self) -> f32 {
+        (self.content.height - self.viewport.height).max(0.0)
+    }
+
+    pub fn clamp_x(&self, offset: f32) -> f32 {
+        offset.clamp(0.0, self.max_offset_x())
+    }
+
+    pub fn clamp_y(&self, offset: f32) -> f32 {
+        offset.clamp(0.0, self.max_offset_y())
+    }
+
+    /// True if only the width overflows, so paging moves along x
+    pub fn is_horizontal(&self) -> bool {
+        self.max_offset_y() == 0.0 && self.max_offset_x() > 0.0
+    }
+
+    /// Number of pages: the list's pages in paged mode, otherwise
+    /// viewport-sized pages along the paging axis
+    pub fn page_count(&self) -> usize {
+        if let Some(pages) = self.pages {
+            return pages.count;
+        }
+        let (content, viewport) = if self.is_horizontal() {
+            (self.content.width, self.viewport.width)
+        } else {
+            (self.content.height, self.viewport.height)
+        };
+        if viewport <= 0.0 {
+            return 1;
+        }
+        ((content / viewport).ceil() as usize).max(1)
+    }
+
+    /// Zero-based page at the current offset; the last page once scrolled
+    /// to the end, even if the content isn't a whole number of pages
+    pub fn current_page(&self) -> usize {
+        if let Some(pages) = self.pages {
+            return pages.current;
+        }
+        let (offset, max_offset, viewport) = if self.is_horizontal() {
+            (self.offset_x, self.max_offset_x(), self.viewport.width)
+        } else {
+            (self.offset_y, self.max_offset_y(), self.viewport.height)
+        };
+        let last = self.page_count() - 1;
+        if max_offset > 0.0 && offset >= max_offset {
+            return last;
+        }
+        if viewport <= 0.0 {
+            return 0;
+        }
+        ((offset / viewport).round() as usize).min(last)
+    }
+}
+
+impl LayoutTree {
+    /// Scroll metrics of the `ScrollView`/`ListView` at `index`
+    pub fn scroll_metrics(&self, index: usize) -> Option<ScrollMetrics> {
+        let node = self.nodes.get(index)?;
+        let (offset_x, offset_y, content_width, content_height, pages) = match node.data {
+            Some(NodeData::ScrollView {
+                scroll_offset,
+                scroll_offset_x,
+                content_height,
+                content_width,
+            }) => (
+                scroll_offset_x,
+                scroll_offset,
+                content_width,
+                content_height,
+                None,
+            ),
+            Some(NodeData::ListView {
+                scroll_offset,
+                scroll_offset_x,
+                content_height,
+                content_width,
+                pages,
+                ..
+            }) => (
+                scroll_offset_x,
+                scroll_offset,
+                content_width,
+                content_height,
+                pages,
+            ),
+            _ => return None,
+        };
+
+        Some(ScrollMetrics {
+            viewport: Size::new(node.rect.width, node.rect.height),
+            content: Size::new(content_width, content_height),
+            offset_x,
+            offset_y,
+            pages,
+        })
+    }
+
+    /// Scroll metrics of the `ScrollView`/`ListView` with the given key
+    pub fn scroll_metrics_of(&self, key: &str) -> Option<ScrollMetrics> {
+        self.index_of(key)
+            .and_then(|index| self.scroll_metrics(index))
+    }
+}
@@ -1,14 +1,182 @@
+use std::collections::HashMap;
+
+use crate::error::{Error, Result};
+use crate::focus::{FocusId, FocusableRect, ScopeId};
+use crate::nodes::Node;
 use crate::style::Rect;
 
 use super::node_data::NodeData;
 
+/// Flattened layout of a `Node` tree, in pre-order.
+///
+/// `nodes[0]` is the root, and each subtree occupies a contiguous range, so
+/// the renderer can walk it in lockstep with the `Node` tree.
 #[derive(Debug, Clone)]
 pub struct LayoutTree {
     pub nodes: Vec<LayoutNode>,
+    keys: HashMap<String, usize>,
 }
 
 #[derive(Debug, Clone)]
 pub struct LayoutNode {
+    /// Position in root coordinates, before any scroll offsets are applied
     pub rect: Rect,
     pub data: Option<NodeData>,
+    pub key: Option<String>,
+    pub focus_id: Option<FocusId>,
+    pub focus_scope: Option<ScopeId>,
+    /// False if this node or one of its ancestors is hidden or invisible
+    pub visible: bool,
+    /// True for scroll containers and views with `clip_children`
+    pub clips_children: bool,
+    pub parent: Option<usize>,
+    pub children: Vec<usize>,
+}
+
+impl LayoutTree {
+    pub(crate) fn new(nodes: Vec<LayoutNode>) -> Self {
+        let mut keys = HashMap::new();
+        for (index, node) in nodes.iter().enumerate() {
+            if let Some(key) = &node.key {
+                // first node in tree order wins if a key is reused
+                keys.entry(key.clone()).or_insert(index);
+            }
+        }
+
+        Self { nodes, keys }
+    }
+
+    pub fn root(&self) -> Option<&LayoutNode> {
+        self.nodes.first()
+    }
+
+    pub fn get(&self, index: usize) -> Option<&LayoutNode> {
+        self.nodes.get(index)
+    }
+
+    pub fn index_of(&self, key: &str) -> Option<usize> {
+        self.keys.get(key).copied()
+    }
+
+    pub fn get_by_key(&self, key: &str) -> Option<&LayoutNode> {
+        self.index_of(key).and_then(|index| self.nodes.get(index))
+    }
+
+    /// Rect of the node with the given key, in root coordinates
+    pub fn rect_of(&self, key: &str) -> Option<Rect> {
+        self.get_by_key(key).map(|node| node.rect)
+    }
+
+    pub fn parent(&self, index: usize) -> Option<usize> {
+        self.nodes.get(index).and_then(|node| node.parent)
+    }
+
+    pub fn children(&self, index: usize) -> &[usize] {
+        self.nodes
+            .get(index)
+            .map(|node| node.children.as_slice())
+            .unwrap_or(&[])
+    }
+
+    /// Indices from the root down to `index`, inclusive
+    pub fn path_to(&self, index: usize) -> Vec<usize> {
+        let mut path = Vec::new();
+        let mut current = Some(index).filter(|&i| i < self.nodes.len());
+        while let Some(i) = current {
+            path.push(i);
+            current = self.nodes[i].parent;
+        }
+        path.reverse();
+        path
+    }
+
+    /// Rect of a node on screen, after the scroll offsets of its
+    /// `ScrollView`/`ListView` ancestors
+    pub fn screen_rect(&self, index: usize) -> Option<Rect> {
+        let mut rect = self.nodes.get(index)?.rect;
+        let mut current = self.nodes[index].parent;
+        while let Some(i) = current {
+            if let Some(metrics) = self.scroll_metrics(i) {
+                rect.x -= metrics.offset_x;
+                rect.y -= metrics.offset_y;
+            }
+            current = self.nodes[i].parent;
+        }
+        Some(rect)
+    }
+
+    /// Visible nodes with a focus id, in tree order, with their screen rects.
+    ///
+    /// Nodes scrolled out of their container are included, so focus can
+    /// move to them and the app can scroll them into view.
+    pub fn focusables(&self) -> Vec<FocusableRect> {
+        self.nodes
+            .iter()
+            .enumerate()
+            .filter(|(_, node)| node.visible)
+            .filter_map(|(index, node)| {
+                let id = node.focus_id?;
+                let scopes = self
+                    .path_to(index)
+                    .into_iter()
+                    .filter_map(|i| self.nodes[i].focus_scope)
+                    .collect();
+                self.screen_rect(index)
+                    .map(|rect| FocusableRect { id, rect, scopes })
+            })
+            .collect()
+    }
+
+    /// Index of the node with the given focus id
+    pub fn index_of_focus(&self, id: FocusId) -> Option<usize> {
+        self.nodes.iter().position(|node| node.focus_id == Some(id))
+    }
+
+    /// Check that this layout was computed for `root`: the same number of
+    /// nodes, and the same kind of node with the same number of children at
+    /// every index.
+    pub fn validate(&self, root: &Node) -> Result<()> {
+        let expected = self.validate_node(root, 0)?;
+        if expected != self.nodes.len() {
+            return Err(Error::ExtraLayoutNodes {
+                expected,
+                found: self.nodes.len(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Returns the index just past the validated subtree
+    fn validate_node(&self, node: &Node, index: usize) -> Result<usize> {
+        let layout_node = self
+            .nodes
+            .get(index)
+            .ok_or(Error::MissingLayoutNode { index })?;
+
+        let found = layout_node
+            .data
+            .as_ref()
+            .map_or("empty node", NodeData::kind_name);
+        if found != node.kind_name() {
+            return Err(Error::LayoutKindMismatch {
+                index,
+                expected: node.kind_name(),
+                found,
+            });
+        }
+
+        if layout_node.children.len() != node.children().len() {
+            return Err(Error::LayoutChildCountMismatch {
+                index,
+                expected: node.children().len(),
+                found: layout_node.children.len(),
+            });
+        }
+
+        let mut next = index + 1;
+        for child in node.children() {
+            next = self.validate_node(child, next)?;
+        }
+        Ok(next)
+    }
 }
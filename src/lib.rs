@@ -1,22 +1,39 @@
+pub mod app;
+pub mod document;
+mod error;
 mod font;
 pub mod focus;
+pub mod input;
 mod layout;
 pub mod nodes;
 mod render;
+pub mod scroll;
 mod style;
 pub mod text;
+#[cfg(test)]
+mod test_util;
 
+pub use error::{Error, Result};
 pub use font::Fonts;
-pub use layout::{LayoutEngine, LayoutNode, LayoutTree};
+pub use layout::{
+    HitTestResult, LayoutEngine, LayoutNode, LayoutTree, ListItemHit, ListPages, ScrollMetrics,
+};
 pub use render::{DeviceAPI, DisplayMode, RenderTarget, Renderer, Rotation};
 pub use style::*;
 
 pub mod prelude {
-    pub use crate::focus::{FocusId, FocusState, FocusableRect};
+    pub use crate::app::{App, Component, Components, Navigator, Runtime, Scheduler};
+    pub use crate::document::{Block, Document};
+    pub use crate::focus::{FocusDirection, FocusId, FocusState, FocusableRect, ScopeId};
     pub use crate::font::Fonts;
-    pub use crate::layout::{LayoutEngine, LayoutTree};
+    pub use crate::input::{
+        Action, Gesture, GestureRecognizer, InputEvent, InputSource, KeyState, Keymap,
+        ScriptedInput, SwipeDirection, TouchPhase,
+    };
+    pub use crate::layout::{LayoutEngine, LayoutTree, ListPages, ScrollMetrics};
     pub use crate::nodes::*;
     pub use crate::render::{DeviceAPI, DisplayMode, RenderTarget, Renderer, Rotation};
+    pub use crate::scroll::ScrollController;
     pub use crate::style::*;
-    pub use crate::text::{line_height, measure_text_width, TextPaginator};
+    pub use crate::text::{line_height, measure_text_width, TextPage, TextPaginator};
 }
@@ -1,9 +1,10 @@
 use crate::{
+    focus::FocusId,
     nodes::Node,
-    style::{Dimension, ImageFit},
+    style::{Dimension, Display, FocusIndicator, ImageFit, Visibility},
 };
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum ImageSource {
     Path(String),
     Bytes(Vec<u8>),
@@ -15,6 +16,13 @@ pub struct ImageNode {
     pub width: Dimension,
     pub height: Dimension,
     pub fit: ImageFit,
+    pub display: Display,
+    pub visibility: Visibility,
+    pub key: Option<String>,
+    /// Registered with `FocusState` by `FocusState::update_from_layout`
+    pub focus_id: Option<FocusId>,
+    /// Replaces the renderer's focus indicator for this node
+    pub focus_indicator: Option<FocusIndicator>,
 }
 
 impl Default for ImageNode {
@@ -24,6 +32,11 @@ impl Default for ImageNode {
             width: Dimension::Auto,
             height: Dimension::Auto,
             fit: ImageFit::Contain,
+            display: Display::Flex,
+            visibility: Visibility::Visible,
+            key: None,
+            focus_id: None,
+            focus_indicator: None,
         }
     }
 }
@@ -67,6 +80,41 @@ impl Image {
         self
     }
 
+    pub fn display(mut self, display: Display) -> Self {
+        self.node.display = display;
+        self
+    }
+
+    pub fn hidden(mut self) -> Self {
+        self.node.display = Display::None;
+        self
+    }
+
+    pub fn visibility(mut self, visibility: Visibility) -> Self {
+        self.node.visibility = visibility;
+        self
+    }
+
+    pub fn invisible(mut self) -> Self {
+        self.node.visibility = Visibility::Invisible;
+        self
+    }
+
+    pub fn key(mut self, key: impl Into<String>) -> Self {
+        self.node.key = Some(key.into());
+        self
+    }
+
+    pub fn focusable(mut self, id: FocusId) -> Self {
+        self.node.focus_id = Some(id);
+        self
+    }
+
+    pub fn focus_indicator(mut self, indicator: FocusIndicator) -> Self {
+        self.node.focus_indicator = Some(indicator);
+        self
+    }
+
     pub fn build(self) -> Node {
         Node::Image(self.node)
     }
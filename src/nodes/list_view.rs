@@ -1,12 +1,38 @@
 
-use crate::{nodes::Node, Align, Color, Dimension, FlexDirection, Justify};
+use crate::{
+    focus::FocusId, nodes::Node, Align, Color, Dimension, Display, FlexDirection, FocusIndicator,
+    Justify, ScrollAlign, ScrollIndicator, Visibility,
+};
 
+/// A list child with an id that identifies it independently of its position
 #[derive(Debug, Clone)]
 pub struct ListItem {
     pub node: Node,
     pub id: u32,
 }
 
+impl ListItem {
+    pub fn new(id: u32, node: impl Into<Node>) -> Self {
+        Self {
+            node: node.into(),
+            id,
+        }
+    }
+}
+
+/// The part of a virtualized list that was built, see `VirtualList`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VirtualWindow {
+    /// Number of items in the whole list
+    pub item_count: usize,
+    /// Item index of `children[0]`
+    pub first: usize,
+    /// Space reserved along the main axis for the items before `first`
+    pub leading: f32,
+    /// Space reserved along the main axis for the items after the last child
+    pub trailing: f32,
+}
+
 #[derive(Debug, Clone)]
 pub struct ListViewNode {
     pub children: Vec<Node>,
@@ -18,9 +44,35 @@ pub struct ListViewNode {
     pub background: Option<Color>,
     pub width: Dimension,
     pub height: Dimension,
+    /// Item index of the selected child, counted from the start of the
+    /// whole list for virtualized lists
     pub selected_index: Option<usize>,
     pub scroll_offset: f32,
+    pub scroll_offset_x: f32,
     pub selected_background: Color,
+    /// Adjust the scroll offset during layout so the selected child is
+    /// visible. The adjusted offset is in the layout's `ScrollMetrics`; feed
+    /// it back with `ScrollController::sync` so the next frame starts there.
+    pub auto_scroll: bool,
+    pub scroll_align: ScrollAlign,
+    /// Show one page of whole items at a time instead of scrolling by pixels
+    pub paged: bool,
+    /// Page shown in paged mode, unless auto-scroll moves to the selection
+    pub page: usize,
+    pub scroll_indicator: ScrollIndicator,
+    pub indicator_color: Color,
+    /// Ids of the children added as `ListItem`s, by position
+    pub item_ids: Vec<Option<u32>>,
+    pub window: Option<VirtualWindow>,
+    pub display: Display,
+    pub visibility: Visibility,
+    pub key: Option<String>,
+    /// Registered with `FocusState` by `FocusState::update_from_layout`
+    pub focus_id: Option<FocusId>,
+    /// Replaces the renderer's focus indicator for this node
+    pub focus_indicator: Option<FocusIndicator>,
+    /// Background while focused, instead of `background`
+    pub focused_background: Option<Color>,
 }
 
 impl Default for ListViewNode {
@@ -37,14 +89,36 @@ impl Default for ListViewNode {
             height: Dimension::Auto,
             selected_index: None,
             scroll_offset: 0.0,
+            scroll_offset_x: 0.0,
             selected_background: Color::Gray(220),
+            auto_scroll: true,
+            scroll_align: ScrollAlign::Nearest,
+            paged: false,
+            page: 0,
+            scroll_indicator: ScrollIndicator::None,
+            indicator_color: Color::Black,
+            item_ids: Vec::new(),
+            window: None,
+            display: Display::Flex,
+            visibility: Visibility::Visible,
+            key: None,
+            focus_id: None,
+            focus_indicator: None,
+            focused_background: None,
         }
     }
 }
 
+impl ListViewNode {
+    /// Item index of the first child
+    pub fn first_item(&self) -> usize {
+        self.window.map_or(0, |window| window.first)
+    }
+}
+
 #[derive(Debug, Clone, Default)]
 pub struct ListView {
-    node: ListViewNode,
+    pub(super) node: ListViewNode,
 }
 
 impl ListView {
@@ -120,23 +194,118 @@ impl ListView {
         self
     }
 
+    pub fn scroll_offset_x(mut self, offset: f32) -> Self {
+        self.node.scroll_offset_x = offset.max(0.0);
+        self
+    }
+
     pub fn selected_background(mut self, color: Color) -> Self {
         self.node.selected_background = color;
         self
     }
 
+    pub fn auto_scroll(mut self, enabled: bool) -> Self {
+        self.node.auto_scroll = enabled;
+        self
+    }
+
+    pub fn scroll_align(mut self, align: ScrollAlign) -> Self {
+        self.node.scroll_align = align;
+        self
+    }
+
+    pub fn paged(mut self, paged: bool) -> Self {
+        self.node.paged = paged;
+        self
+    }
+
+    pub fn page(mut self, page: usize) -> Self {
+        self.node.page = page;
+        self
+    }
+
+    pub fn scroll_indicator(mut self, indicator: ScrollIndicator) -> Self {
+        self.node.scroll_indicator = indicator;
+        self
+    }
+
+    pub fn indicator_color(mut self, color: Color) -> Self {
+        self.node.indicator_color = color;
+        self
+    }
+
     pub fn child(mut self, node: impl Into<Node>) -> Self {
         self.node.children.push(node.into());
+        self.node.item_ids.push(None);
         self
     }
 
     pub fn children(mut self, nodes: impl IntoIterator<Item = impl Into<Node>>) -> Self {
         for node in nodes {
             self.node.children.push(node.into());
+            self.node.item_ids.push(None);
         }
         self
     }
 
+    pub fn item(mut self, item: ListItem) -> Self {
+        self.node.children.push(item.node);
+        self.node.item_ids.push(Some(item.id));
+        self
+    }
+
+    pub fn items(mut self, items: impl IntoIterator<Item = ListItem>) -> Self {
+        for item in items {
+            self = self.item(item);
+        }
+        self
+    }
+
+    pub(super) fn window(mut self, window: VirtualWindow) -> Self {
+        self.node.window = Some(window);
+        self
+    }
+
+    pub fn display(mut self, display: Display) -> Self {
+        self.node.display = display;
+        self
+    }
+
+    pub fn hidden(mut self) -> Self {
+        self.node.display = Display::None;
+        self
+    }
+
+    pub fn visibility(mut self, visibility: Visibility) -> Self {
+        self.node.visibility = visibility;
+        self
+    }
+
+    pub fn invisible(mut self) -> Self {
+        self.node.visibility = Visibility::Invisible;
+        self
+    }
+
+    pub fn key(mut self, key: impl Into<String>) -> Self {
+        self.node.key = Some(key.into());
+        self
+    }
+
+    pub fn focusable(mut self, id: FocusId) -> Self {
+        self.node.focus_id = Some(id);
+        self
+    }
+
+    pub fn focus_indicator(mut self, indicator: FocusIndicator) -> Self {
+        self.node.focus_indicator = Some(indicator);
+        self
+    }
+
+    pub fn focused_background(mut self, color: Color) -> Self {
+        self.node.focused_background = Some(color);
+        self
+    }
+
     pub fn build(self) -> Node {
         Node::ListView(self.node)
     }
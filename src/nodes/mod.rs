@@ -3,12 +3,17 @@ mod list_view;
 mod scroll_view;
 mod text;
 mod view;
+mod virtual_list;
 
 pub use image::{Image, ImageNode, ImageSource};
-pub use list_view::{ListView, ListViewNode};
+pub use list_view::{ListItem, ListView, ListViewNode, VirtualWindow};
 pub use scroll_view::{ScrollView, ScrollViewNode};
 pub use text::{Text, TextNode};
 pub use view::{View, ViewNode};
+pub use virtual_list::VirtualList;
+
+use crate::focus::{FocusId, ScopeId};
+use crate::style::{Display, FocusIndicator, Visibility};
 
 #[derive(Debug, Clone)]
 pub enum Node {
@@ -18,3 +23,93 @@ pub enum Node {
     ScrollView(ScrollViewNode),
     ListView(ListViewNode),
 }
+
+impl Node {
+    pub fn children(&self) -> &[Node] {
+        match self {
+            Node::View(view) => &view.children,
+            Node::ScrollView(scroll) => &scroll.children,
+            Node::ListView(list) => &list.children,
+            Node::Text(_) | Node::Image(_) => &[],
+        }
+    }
+
+    pub fn display(&self) -> Display {
+        match self {
+            Node::View(view) => view.display,
+            Node::Text(text) => text.display,
+            Node::Image(img) => img.display,
+            Node::ScrollView(scroll) => scroll.display,
+            Node::ListView(list) => list.display,
+        }
+    }
+
+    pub fn visibility(&self) -> Visibility {
+        match self {
+            Node::View(view) => view.visibility,
+            Node::Text(text) => text.visibility,
+            Node::Image(img) => img.visibility,
+            Node::ScrollView(scroll) => scroll.visibility,
+            Node::ListView(list) => list.visibility,
+        }
+    }
+
+    pub(crate) fn kind_name(&self) -> &'static str {
+        match self {
+            Node::View(_) => "View",
+            Node::Text(_) => "Text",
+            Node::Image(_) => "Image",
+            Node::ScrollView(_) => "ScrollView",
+            Node::ListView(_) => "ListView",
+        }
+    }
+
+    pub fn key(&self) -> Option<&str> {
+        match self {
+            Node::View(view) => view.key.as_deref(),
+            Node::Text(text) => text.key.as_deref(),
+            Node::Image(img) => img.key.as_deref(),
+            Node::ScrollView(scroll) => scroll.key.as_deref(),
+            Node::ListView(list) => list.key.as_deref(),
+        }
+    }
+
+    pub fn focus_id(&self) -> Option<FocusId> {
+        match self {
+            Node::View(view) => view.focus_id,
+            Node::Text(text) => text.focus_id,
+            Node::Image(img) => img.focus_id,
+            Node::ScrollView(scroll) => scroll.focus_id,
+            Node::ListView(list) => list.focus_id,
+        }
+    }
+
+    /// Only views open focus scopes
+    pub fn focus_scope(&self) -> Option<ScopeId> {
+        match self {
+            Node::View(view) => view.focus_scope,
+            _ => None,
+        }
+    }
+
+    pub fn focus_indicator(&self) -> Option<FocusIndicator> {
+        match self {
+            Node::View(view) => view.focus_indicator,
+            Node::Text(text) => text.focus_indicator,
+            Node::Image(img) => img.focus_indicator,
+            Node::ScrollView(scroll) => scroll.focus_indicator,
+            Node::ListView(list) => list.focus_indicator,
+        }
+    }
+
+    /// Whether the node and its subtree should be painted
+    pub fn is_painted(&self) -> bool {
+        self.display() != Display::None && self.visibility() == Visibility::Visible
+    }
+
+    /// Number of nodes in this subtree, including the node itself.
+    /// This is how many entries the subtree occupies in `LayoutTree::nodes`.
+    pub fn subtree_len(&self) -> usize {
+        1 + self.children().iter().map(Node::subtree_len).sum::<usize>()
+    }
+}
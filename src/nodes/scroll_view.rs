@@ -1,4 +1,7 @@
-use crate::{nodes::Node, Align, Color, Dimension, FlexDirection, Justify};
+use crate::{
+    focus::FocusId, nodes::Node, Align, Color, Dimension, Display, FlexDirection, FocusIndicator,
+    Justify, ScrollIndicator, Visibility,
+};
 
 #[derive(Debug, Clone)]
 pub struct ScrollViewNode {
@@ -13,6 +16,19 @@ pub struct ScrollViewNode {
     pub height: Dimension,
     /// Vertical scroll offset in pixels (how far the content is scrolled down)
     pub scroll_offset: f32,
+    /// Horizontal scroll offset in pixels (how far the content is scrolled right)
+    pub scroll_offset_x: f32,
+    pub scroll_indicator: ScrollIndicator,
+    pub indicator_color: Color,
+    pub display: Display,
+    pub visibility: Visibility,
+    pub key: Option<String>,
+    /// Registered with `FocusState` by `FocusState::update_from_layout`
+    pub focus_id: Option<FocusId>,
+    /// Replaces the renderer's focus indicator for this node
+    pub focus_indicator: Option<FocusIndicator>,
+    /// Background while focused, instead of `background`
+    pub focused_background: Option<Color>,
 }
 
 impl Default for ScrollViewNode {
@@ -28,6 +44,15 @@ impl Default for ScrollViewNode {
             width: Dimension::Auto,
             height: Dimension::Auto,
             scroll_offset: 0.0,
+            scroll_offset_x: 0.0,
+            scroll_indicator: ScrollIndicator::None,
+            indicator_color: Color::Black,
+            display: Display::Flex,
+            visibility: Visibility::Visible,
+            key: None,
+            focus_id: None,
+            focus_indicator: None,
+            focused_background: None,
         }
     }
 }
@@ -52,6 +77,16 @@ impl ScrollView {
         }
     }
 
+    /// Create a horizontal scroll view
+    pub fn horizontal() -> Self {
+        Self {
+            node: ScrollViewNode {
+                direction: FlexDirection::Row,
+                ..Default::default()
+            },
+        }
+    }
+
     pub fn direction(mut self, direction: FlexDirection) -> Self {
         self.node.direction = direction;
         self
@@ -98,6 +133,22 @@ impl ScrollView {
         self
     }
 
+    /// Set the horizontal scroll offset in pixels
+    pub fn scroll_offset_x(mut self, offset: f32) -> Self {
+        self.node.scroll_offset_x = offset.max(0.0);
+        self
+    }
+
+    pub fn scroll_indicator(mut self, indicator: ScrollIndicator) -> Self {
+        self.node.scroll_indicator = indicator;
+        self
+    }
+
+    pub fn indicator_color(mut self, color: Color) -> Self {
+        self.node.indicator_color = color;
+        self
+    }
+
     pub fn child(mut self, node: impl Into<Node>) -> Self {
         self.node.children.push(node.into());
         self
@@ -110,6 +161,46 @@ impl ScrollView {
         self
     }
 
+    pub fn display(mut self, display: Display) -> Self {
+        self.node.display = display;
+        self
+    }
+
+    pub fn hidden(mut self) -> Self {
+        self.node.display = Display::None;
+        self
+    }
+
+    pub fn visibility(mut self, visibility: Visibility) -> Self {
+        self.node.visibility = visibility;
+        self
+    }
+
+    pub fn invisible(mut self) -> Self {
+        self.node.visibility = Visibility::Invisible;
+        self
+    }
+
+    pub fn key(mut self, key: impl Into<String>) -> Self {
+        self.node.key = Some(key.into());
+        self
+    }
+
+    pub fn focusable(mut self, id: FocusId) -> Self {
+        self.node.focus_id = Some(id);
+        self
+    }
+
+    pub fn focus_indicator(mut self, indicator: FocusIndicator) -> Self {
+        self.node.focus_indicator = Some(indicator);
+        self
+    }
+
+    pub fn focused_background(mut self, color: Color) -> Self {
+        self.node.focused_background = Some(color);
+        self
+    }
+
     pub fn build(self) -> Node {
         Node::ScrollView(self.node)
     }
@@ -1,5 +1,6 @@
+use crate::focus::FocusId;
 use crate::nodes::Node;
-use crate::style::{Color, TextAlign, TextOverflow, TextWrap};
+use crate::style::{Color, Display, FocusIndicator, TextAlign, TextOverflow, TextWrap, Visibility};
 use crate::TextFont;
 
 #[derive(Debug, Clone)]
@@ -11,6 +12,15 @@ pub struct TextNode {
     pub overflow: TextOverflow,
     pub align: TextAlign,
     pub font: TextFont,
+    pub display: Display,
+    pub visibility: Visibility,
+    pub key: Option<String>,
+    /// Registered with `FocusState` by `FocusState::update_from_layout`
+    pub focus_id: Option<FocusId>,
+    /// Replaces the renderer's focus indicator for this node
+    pub focus_indicator: Option<FocusIndicator>,
+    /// Text color while focused, instead of `color`
+    pub focused_color: Option<Color>,
 }
 
 impl Default for TextNode {
@@ -23,6 +33,12 @@ impl Default for TextNode {
             overflow: TextOverflow::Clip,
             align: TextAlign::Left,
             font: TextFont::NotosansRegular,
+            display: Display::Flex,
+            visibility: Visibility::Visible,
+            key: None,
+            focus_id: None,
+            focus_indicator: None,
+            focused_color: None,
         }
     }
 }
@@ -112,6 +128,46 @@ impl Text {
         self
     }
 
+    pub fn display(mut self, display: Display) -> Self {
+        self.node.display = display;
+        self
+    }
+
+    pub fn hidden(mut self) -> Self {
+        self.node.display = Display::None;
+        self
+    }
+
+    pub fn visibility(mut self, visibility: Visibility) -> Self {
+        self.node.visibility = visibility;
+        self
+    }
+
+    pub fn invisible(mut self) -> Self {
+        self.node.visibility = Visibility::Invisible;
+        self
+    }
+
+    pub fn key(mut self, key: impl Into<String>) -> Self {
+        self.node.key = Some(key.into());
+        self
+    }
+
+    pub fn focusable(mut self, id: FocusId) -> Self {
+        self.node.focus_id = Some(id);
+        self
+    }
+
+    pub fn focus_indicator(mut self, indicator: FocusIndicator) -> Self {
+        self.node.focus_indicator = Some(indicator);
+        self
+    }
+
+    pub fn focused_color(mut self, color: Color) -> Self {
+        self.node.focused_color = Some(color);
+        self
+    }
+
     pub fn build(self) -> Node {
         Node::Text(self.node)
     }
@@ -1,4 +1,8 @@
-use crate::{nodes::Node, Align, Color, Dimension, FlexDirection, Justify};
+use crate::{
+    focus::{FocusId, ScopeId},
+    nodes::Node,
+    Align, Color, Dimension, Display, FlexDirection, FocusIndicator, Justify, Visibility,
+};
 
 #[derive(Debug, Clone)]
 pub struct ViewNode {
@@ -11,6 +15,19 @@ pub struct ViewNode {
     pub background: Option<Color>,
     pub width: Dimension,
     pub height: Dimension,
+    /// Clip the children to this view's rect
+    pub clip_children: bool,
+    pub display: Display,
+    pub visibility: Visibility,
+    pub key: Option<String>,
+    /// Registered with `FocusState` by `FocusState::update_from_layout`
+    pub focus_id: Option<FocusId>,
+    /// Replaces the renderer's focus indicator for this node
+    pub focus_indicator: Option<FocusIndicator>,
+    /// Background while focused, instead of `background`
+    pub focused_background: Option<Color>,
+    /// Focusables inside belong to this scope, see `FocusState::enter_scope`
+    pub focus_scope: Option<ScopeId>,
 }
 
 impl Default for ViewNode {
@@ -25,6 +42,14 @@ impl Default for ViewNode {
             background: None,
             width: Dimension::Auto,
             height: Dimension::Auto,
+            clip_children: false,
+            display: Display::Flex,
+            visibility: Visibility::Visible,
+            key: None,
+            focus_id: None,
+            focus_indicator: None,
+            focused_background: None,
+            focus_scope: None,
         }
     }
 }
@@ -97,6 +122,11 @@ impl View {
         self
     }
 
+    pub fn clip_children(mut self, clip: bool) -> Self {
+        self.node.clip_children = clip;
+        self
+    }
+
     pub fn child(mut self, node: impl Into<Node>) -> Self {
         self.node.children.push(node.into());
         self
@@ -109,6 +139,51 @@ impl View {
         self
     }
 
+    pub fn display(mut self, display: Display) -> Self {
+        self.node.display = display;
+        self
+    }
+
+    pub fn hidden(mut self) -> Self {
+        self.node.display = Display::None;
+        self
+    }
+
+    pub fn visibility(mut self, visibility: Visibility) -> Self {
+        self.node.visibility = visibility;
+        self
+    }
+
+    pub fn invisible(mut self) -> Self {
+        self.node.visibility = Visibility::Invisible;
+        self
+    }
+
+    pub fn key(mut self, key: impl Into<String>) -> Self {
+        self.node.key = Some(key.into());
+        self
+    }
+
+    pub fn focusable(mut self, id: FocusId) -> Self {
+        self.node.focus_id = Some(id);
+        self
+    }
+
+    pub fn focus_indicator(mut self, indicator: FocusIndicator) -> Self {
+        self.node.focus_indicator = Some(indicator);
+        self
+    }
+
+    pub fn focused_background(mut self, color: Color) -> Self {
+        self.node.focused_background = Some(color);
+        self
+    }
+
+    pub fn focus_scope(mut self, scope: ScopeId) -> Self {
+        self.node.focus_scope = Some(scope);
+        self
+    }
+
     pub fn build(self) -> Node {
         Node::View(self.node)
     }
@@ -0,0 +1,367 @@
+use crate::layout::{align_scroll, LayoutTree};
+use crate::style::{Dimension, FlexDirection};
+
+use super::list_view::{ListItem, ListView, VirtualWindow};
+
+/// Builds only the visible part of a long `ListView`.
+///
+/// Keep one per list across frames. `build` creates the items in view at
+/// the list's scroll offset, plus `overscan` items either side, and reserves
+/// space for the rest using their measured or estimated sizes. Call
+/// `measure` after each layout so later frames use the real sizes.
+///
+/// Paged lists need every item built to find their pages, so layout rejects
+/// a paged list built by a `VirtualList`.
+#[derive(Debug, Clone)]
+pub struct VirtualList {
+    item_count: usize,
+    estimated_size: f32,
+    overscan: usize,
+    /// Main-axis size of each item, once it has been laid out
+    sizes: Vec<Option<f32>>,
+    /// Start of each item along the main axis, then the end of the last
+    /// one plus `gap`; empty while out of date with the sizes and gap
+    offsets: Vec<f32>,
+    viewport: Option<f32>,
+    horizontal: bool,
+    gap: f32,
+    window: Option<VirtualWindow>,
+}
+
+impl VirtualList {
+    /// `estimated_size` is the main-axis size assumed for items that
+    /// haven't been laid out yet
+    pub fn new(item_count: usize, estimated_size: f32) -> Self {
+        Self {
+            item_count,
+            estimated_size,
+            overscan: 2,
+            sizes: vec![None; item_count],
+            offsets: Vec::new(),
+            viewport: None,
+            horizontal: false,
+            gap: 0.0,
+            window: None,
+        }
+    }
+
+    /// Extra items to build on each side of the visible ones
+    pub fn overscan(mut self, items: usize) -> Self {
+        self.overscan = items;
+        self
+    }
+
+    /// Main-axis size of the list to assume until it has been measured,
+    /// for lists without a `Px` size
+    pub fn viewport(mut self, px: f32) -> Self {
+        self.viewport = Some(px);
+        self
+    }
+
+    pub fn item_count(&self) -> usize {
+        self.item_count
+    }
+
+    /// Change the number of items, keeping the sizes measured for the
+    /// items that remain
+    pub fn set_item_count(&mut self, item_count: usize) {
+        self.item_count = item_count;
+        self.sizes.resize(item_count, None);
+        self.offsets.clear();
+    }
+
+    /// Forget measured sizes, e.g. after the items' contents changed
+    pub fn invalidate(&mut self) {
+        self.sizes.iter_mut().for_each(|size| *size = None);
+        self.offsets.clear();
+    }
+
+    /// Window built by the last call to `build`
+    pub fn window(&self) -> Option<VirtualWindow> {
+        self.window
+    }
+
+    pub fn item_size(&self, index: usize) -> f32 {
+        self.sizes
+            .get(index)
+            .copied()
+            .flatten()
+            .unwrap_or(self.estimated_size)
+    }
+
+    /// Main-axis offset of item `index` within the list's content, not
+    /// counting its padding
+    pub fn item_offset(&self, index: usize) -> f32 {
+        let index = index.min(self.item_count);
+        match self.offsets.get(index) {
+            Some(&offset) => offset,
+            None => (0..index).map(|i| self.item_size(i) + self.gap).sum(),
+        }
+    }
+
+    /// Main-axis size of all items and the gaps between them
+    pub fn content_size(&self) -> f32 {
+        match self.item_count {
+            0 => 0.0,
+            count => self.item_offset(count) - self.gap,
+        }
+    }
+
+    /// Fill `list` with the items in view, creating each with `item`.
+    ///
+    /// `list` should have no children of its own; its direction, gap,
+    /// padding and scroll offset decide which items are built.
+    pub fn build(&mut self, list: ListView, mut item: impl FnMut(usize) -> ListItem) -> ListView {
+        let node = &list.node;
+        self.horizontal = node.direction == FlexDirection::Row;
+        if self.gap != node.gap {
+            self.gap = node.gap;
+            self.offsets.clear();
+        }
+
+        let (offset, size) = if self.horizontal {
+            (node.scroll_offset_x, node.width)
+        } else {
+            (node.scroll_offset, node.height)
+        };
+        let viewport = match (self.viewport, size) {
+            (Some(px), _) | (None, Dimension::Px(px)) => px,
+            _ => 0.0,
+        };
+
+        // item `i` spans `offsets[i]..offsets[i + 1] - gap`
+        self.update_offsets();
+        let starts = &self.offsets[..self.item_count];
+        let ends = &self.offsets[1..];
+        let content_size = self.content_size();
+
+        // scroll to the selection first, so its item is among those built
+        let offset = match node.selected_index {
+            Some(selected) if node.auto_scroll && selected < self.item_count && viewport > 0.0 => {
+                align_scroll(
+                    node.scroll_align,
+                    offset,
+                    (node.padding + starts[selected], self.item_size(selected)),
+                    (node.padding, viewport - node.padding),
+                )
+                .max(0.0)
+            }
+            _ => offset,
+        };
+
+        let view_start = offset - node.padding;
+        let view_end = view_start + viewport;
+        let first_visible = ends.partition_point(|&end| end - self.gap <= view_start);
+        let end_visible = starts
+            .partition_point(|&start| start < view_end)
+            .max(first_visible);
+
+        let first = first_visible.saturating_sub(self.overscan);
+        let end = (end_visible + self.overscan)
+            .min(self.item_count)
+            .max(first);
+
+        let leading = starts.get(first).copied().unwrap_or(content_size);
+        let built_end = match end {
+            end if end > first => ends[end - 1] - self.gap,
+            _ => leading,
+        };
+        let window = VirtualWindow {
+            item_count: self.item_count,
+            first,
+            leading,
+            trailing: content_size - built_end,
+        };
+        self.window = Some(window);
+
+        list.items((first..end).map(&mut item)).window(window)
+    }
+
+    /// Record the sizes of the items laid out for the list at `index`,
+    /// and the list's own size as the viewport.
+    pub fn measure(&mut self, layout: &LayoutTree, index: usize) {
+        let (Some(node), Some(window)) = (layout.get(index), self.window) else {
+            return;
+        };
+
+        self.viewport = Some(if self.horizontal {
+            node.rect.width
+        } else {
+            node.rect.height
+        });
+        for (position, &child) in node.children.iter().enumerate() {
+            let rect = layout.nodes[child].rect;
+            let measured = if self.horizontal {
+                rect.width
+            } else {
+                rect.height
+            };
+            if let Some(size) = self.sizes.get_mut(window.first + position) {
+                if *size != Some(measured) {
+                    *size = Some(measured);
+                    self.offsets.clear();
+                }
+            }
+        }
+    }
+
+    /// Recompute `offsets` if the sizes or gap changed since the last time
+    fn update_offsets(&mut self) {
+        if self.offsets.len() == self.item_count + 1 {
+            return;
+        }
+        self.offsets.clear();
+        self.offsets.reserve(self.item_count + 1);
+        let mut offset = 0.0;
+        self.offsets.push(offset);
+        for i in 0..self.item_count {
+            offset += self.item_size(i) + self.gap;
+            self.offsets.push(offset);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout::LayoutEngine;
+    use crate::nodes::{Node, View};
+    use crate::style::Size;
+
+    fn row(id: usize) -> ListItem {
+        ListItem::new(id as u32, View::new().height(Dimension::Px(50.0)))
+    }
+
+    fn list(scroll_offset: f32) -> ListView {
+        ListView::vertical()
+            .width(Dimension::Percent(1.0))
+            .height(Dimension::Px(200.0))
+            .scroll_offset(scroll_offset)
+    }
+
+    fn children(node: &Node) -> usize {
+        node.children().len()
+    }
+
+    #[test]
+    fn test_builds_visible_window() {
+        let mut items = VirtualList::new(5000, 50.0).overscan(2);
+        let root = items.build(list(1000.0), row).build();
+
+        // items 20..24 are visible
+        let window = items.window().unwrap();
+        assert_eq!(window.first, 18);
+        assert_eq!(children(&root), 8);
+        assert_eq!(window.leading, 900.0);
+        assert_eq!(window.trailing, (5000.0 - 26.0) * 50.0);
+
+        let layout = LayoutEngine::new()
+            .compute(&root, Size::new(200.0, 400.0))
+            .unwrap();
+        let metrics = layout.scroll_metrics(0).unwrap();
+        assert_eq!(metrics.content.height, 250_000.0);
+        assert_eq!(metrics.offset_y, 1000.0);
+        assert_eq!(layout.nodes[layout.children(0)[2]].rect.y, 1000.0);
+    }
+
+    #[test]
+    fn test_measure_replaces_estimates() {
+        let mut items = VirtualList::new(100, 20.0);
+        let root = items.build(list(0.0), row).build();
+        assert_eq!(children(&root), 12);
+
+        let layout = LayoutEngine::new()
+            .compute(&root, Size::new(200.0, 400.0))
+            .unwrap();
+        items.measure(&layout, 0);
+        assert_eq!(items.item_size(0), 50.0);
+        assert_eq!(items.item_size(50), 20.0);
+
+        let root = items.build(list(0.0), row).build();
+        assert_eq!(children(&root), 6);
+    }
+
+    #[test]
+    fn test_offsets_follow_size_changes() {
+        let mut items = VirtualList::new(1000, 20.0);
+        let root = items.build(list(0.0), row).build();
+        assert_eq!(items.offsets.len(), 1001);
+
+        // measuring the built items at 50px changes their offsets
+        let layout = LayoutEngine::new()
+            .compute(&root, Size::new(200.0, 400.0))
+            .unwrap();
+        items.measure(&layout, 0);
+        assert!(items.offsets.is_empty());
+        let root = items.build(list(0.0), row).build();
+        assert_eq!(items.item_offset(12), 600.0);
+        assert_eq!(items.item_offset(13), 620.0);
+
+        // the same sizes again keep them
+        let layout = LayoutEngine::new()
+            .compute(&root, Size::new(200.0, 400.0))
+            .unwrap();
+        items.measure(&layout, 0);
+        assert_eq!(items.offsets.len(), 1001);
+
+        items.set_item_count(10);
+        items.build(list(0.0).gap(10.0), row);
+        assert_eq!(items.content_size(), 10.0 * 50.0 + 9.0 * 10.0);
+    }
+
+    #[test]
+    fn test_builds_around_selection() {
+        let mut items = VirtualList::new(5000, 50.0);
+        let root = items
+            .build(list(0.0).selected_index(Some(100)), row)
+            .build();
+        let window = items.window().unwrap();
+        assert!(window.first <= 100 && 100 < window.first + children(&root));
+
+        // item 100 spans 5000..5050, at the bottom of the 200px list
+        let layout = LayoutEngine::new()
+            .compute(&root, Size::new(200.0, 400.0))
+            .unwrap();
+        assert_eq!(layout.scroll_metrics(0).unwrap().offset_y, 4850.0);
+        let hit = layout.hit_test(10.0, 190.0).unwrap().list_item.unwrap();
+        assert_eq!(hit.item, 100);
+    }
+
+    #[test]
+    fn test_auto_scroll_to_unbuilt_item() {
+        let mut items = VirtualList::new(5000, 50.0);
+        // selected after the window was built, so item 100 has no child
+        let root = items
+            .build(list(0.0), row)
+            .selected_index(Some(100))
+            .build();
+        let layout = LayoutEngine::new()
+            .compute(&root, Size::new(200.0, 400.0))
+            .unwrap();
+        assert_eq!(layout.scroll_metrics(0).unwrap().offset_y, 4850.0);
+
+        let root = items
+            .build(list(9000.0), row)
+            .selected_index(Some(10))
+            .build();
+        let layout = LayoutEngine::new()
+            .compute(&root, Size::new(200.0, 400.0))
+            .unwrap();
+        assert_eq!(layout.scroll_metrics(0).unwrap().offset_y, 500.0);
+    }
+
+    #[test]
+    fn test_hit_reports_item_id() {
+        let mut items = VirtualList::new(5000, 50.0);
+        let root = items
+            .build(list(1000.0), |i| ListItem::new(i as u32 * 10, row(i).node))
+            .build();
+        let layout = LayoutEngine::new()
+            .compute(&root, Size::new(200.0, 400.0))
+            .unwrap();
+
+        let hit = layout.hit_test(10.0, 60.0).unwrap().list_item.unwrap();
+        assert_eq!(hit.item, 21);
+        assert_eq!(hit.id, Some(210));
+    }
+}
@@ -0,0 +1,56 @@
+use crate::style::{Color, FocusIndicator, Rect};
+
+use super::primitives::fill_rect_clipped;
+use super::target::RenderTarget;
+
+/// Mark `rect` as focused. `width` is the thickness of borders and
+/// underlines; inverting ignores `width` and `color`.
+pub fn render_focus_indicator<T: RenderTarget>(
+    target: &mut T,
+    indicator: FocusIndicator,
+    color: Color,
+    width: f32,
+    rect: &Rect,
+    clip: Option<&Rect>,
+) {
+    let width = width.min(rect.width / 2.0).min(rect.height / 2.0).max(0.0);
+
+    match indicator {
+        FocusIndicator::Invert => invert_rect(target, rect, clip),
+        FocusIndicator::Border => {
+            let (x, y) = (rect.x, rect.y);
+            let (right, bottom) = (rect.x + rect.width, rect.y + rect.height);
+            fill_rect_clipped(target, x, y, rect.width, width, color, clip);
+            fill_rect_clipped(target, x, bottom - width, rect.width, width, color, clip);
+            fill_rect_clipped(target, x, y, width, rect.height, color, clip);
+            fill_rect_clipped(target, right - width, y, width, rect.height, color, clip);
+        }
+        FocusIndicator::Underline => {
+            let y = rect.y + rect.height - width;
+            fill_rect_clipped(target, rect.x, y, rect.width, width, color, clip);
+        }
+        FocusIndicator::None => {}
+    }
+}
+
+fn invert_rect<T: RenderTarget>(target: &mut T, rect: &Rect, clip: Option<&Rect>) {
+    let area = match clip {
+        Some(clip) => match clip.intersect(rect) {
+            Some(area) => area,
+            None => return,
+        },
+        None => *rect,
+    };
+
+    let x_start = area.x.max(0.0) as i32;
+    let y_start = area.y.max(0.0) as i32;
+    let x_end = ((area.x + area.width) as i32).min(target.width() as i32);
+    let y_end = ((area.y + area.height) as i32).min(target.height() as i32);
+
+    for py in y_start..y_end {
+        for px in x_start..x_end {
+            let luma = target.get_pixel(px, py);
+            target.set_pixel(px, py, 255 - luma);
+        }
+    }
+}
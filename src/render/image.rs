@@ -1,4 +1,3 @@
-use crate::layout::LayoutNode;
 use crate::nodes::{ImageNode, ImageSource};
 use crate::style::{ImageFit, Rect};
 
@@ -8,7 +7,7 @@ use super::target::RenderTarget;
 pub fn render_image<T: RenderTarget>(
     target: &mut T,
     img: &ImageNode,
-    layout_node: &LayoutNode,
+    rect: &Rect,
     clip: Option<&Rect>,
 ) {
     let dynamic_img = match &img.source {
@@ -25,7 +24,6 @@ pub fn render_image<T: RenderTarget>(
     let gray_img = dynamic_img.to_luma8();
     let (img_width, img_height) = gray_img.dimensions();
 
-    let rect = &layout_node.rect;
     let container_width = rect.width;
     let container_height = rect.height;
 
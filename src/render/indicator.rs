@@ -0,0 +1,288 @@
+use ab_glyph::{Font, ScaleFont};
+
+use crate::font::Fonts;
+use crate::layout::ScrollMetrics;
+use crate::style::{Color, Rect, ScrollIndicator, TextFont};
+use crate::text::measure_text_width;
+
+use super::primitives::fill_rect_clipped;
+use super::target::RenderTarget;
+use super::text::render_line_clipped;
+
+const INSET: f32 = 2.0;
+const BAR_THICKNESS: f32 = 4.0;
+const MIN_THUMB_LENGTH: f32 = 16.0;
+const ARROW_DEPTH: i32 = 6;
+const LABEL_FONT_SIZE: f32 = 14.0;
+
+#[derive(Clone, Copy)]
+enum Edge {
+    Top,
+    Bottom,
+    Left,
+    Right,
+}
+
+/// Draw a scroll indicator over a scroll container at `rect` on screen,
+/// clipped to `clip`. Nothing is drawn if the content fits the viewport.
+pub fn render_scroll_indicator<T: RenderTarget>(
+    target: &mut T,
+    fonts: &Fonts,
+    indicator: ScrollIndicator,
+    color: Color,
+    rect: &Rect,
+    metrics: &ScrollMetrics,
+    clip: &Rect,
+) {
+    if metrics.max_offset_x() == 0.0 && metrics.max_offset_y() == 0.0 {
+        return;
+    }
+
+    match indicator {
+        ScrollIndicator::None => {}
+        ScrollIndicator::Bar => render_bar(target, color, rect, metrics, clip),
+        ScrollIndicator::PageLabel => render_page_label(target, fonts, color, rect, metrics, clip),
+        ScrollIndicator::Arrows => render_arrows(target, color, rect, metrics, clip),
+    }
+}
+
+fn render_bar<T: RenderTarget>(
+    target: &mut T,
+    color: Color,
+    rect: &Rect,
+    metrics: &ScrollMetrics,
+    clip: &Rect,
+) {
+    let clip = Some(clip);
+    if metrics.is_horizontal() {
+        let (start, length) = thumb(
+            rect.width,
+            metrics.viewport.width / metrics.content.width,
+            metrics.offset_x / metrics.max_offset_x(),
+        );
+        let y = rect.y + rect.height - BAR_THICKNESS;
+        fill_rect_clipped(
+            target,
+            rect.x + start,
+            y,
+            length,
+            BAR_THICKNESS,
+            color,
+            clip,
+        );
+    } else {
+        let (start, length) = thumb(
+            rect.height,
+            metrics.viewport.height / metrics.content.height,
+            metrics.offset_y / metrics.max_offset_y(),
+        );
+        let x = rect.x + rect.width - BAR_THICKNESS;
+        fill_rect_clipped(
+            target,
+            x,
+            rect.y + start,
+            BAR_THICKNESS,
+            length,
+            color,
+            clip,
+        );
+    }
+}
+
+/// Start and length of the thumb within a track of `track` pixels
+fn thumb(track: f32, visible_fraction: f32, progress: f32) -> (f32, f32) {
+    let length = (track * visible_fraction).max(MIN_THUMB_LENGTH).min(track);
+    ((track - length) * progress.clamp(0.0, 1.0), length)
+}
+
+fn render_page_label<T: RenderTarget>(
+    target: &mut T,
+    fonts: &Fonts,
+    color: Color,
+    rect: &Rect,
+    metrics: &ScrollMetrics,
+    clip: &Rect,
+) {
+    let label = format!(
+        "page {}/{}",
+        metrics.current_page() + 1,
+        metrics.page_count()
+    );
+    let font = fonts.get(TextFont::default());
+    let scaled_font = font.as_scaled(LABEL_FONT_SIZE);
+    let width = measure_text_width(font, &label, LABEL_FONT_SIZE);
+    let height = scaled_font.height();
+
+    let x = rect.x + rect.width - width - INSET * 3.0;
+    let y = rect.y + rect.height - height - INSET * 3.0;
+
+    // keep the label readable over the content
+    fill_rect_clipped(
+        target,
+        x - INSET,
+        y - INSET,
+        width + INSET * 2.0,
+        height + INSET * 2.0,
+        Color::White,
+        Some(clip),
+    );
+    render_line_clipped(
+        target,
+        font,
+        &label,
+        x,
+        y + scaled_font.ascent(),
+        LABEL_FONT_SIZE,
+        color.to_luma(),
+        Some(clip),
+    );
+}
+
+fn render_arrows<T: RenderTarget>(
+    target: &mut T,
+    color: Color,
+    rect: &Rect,
+    metrics: &ScrollMetrics,
+    clip: &Rect,
+) {
+    let center_x = rect.x + rect.width / 2.0;
+    let center_y = rect.y + rect.height / 2.0;
+    let right = rect.x + rect.width;
+    let bottom = rect.y + rect.height;
+
+    if metrics.is_horizontal() {
+        if metrics.offset_x > 0.0 {
+            fill_arrow(target, rect.x + INSET, center_y, Edge::Left, color, clip);
+        }
+        if metrics.offset_x < metrics.max_offset_x() {
+            fill_arrow(target, right - INSET, center_y, Edge::Right, color, clip);
+        }
+    } else {
+        if metrics.offset_y > 0.0 {
+            fill_arrow(target, center_x, rect.y + INSET, Edge::Top, color, clip);
+        }
+        if metrics.offset_y < metrics.max_offset_y() {
+            fill_arrow(target, center_x, bottom - INSET, Edge::Bottom, color, clip);
+        }
+    }
+}
+
+/// Solid triangle with its apex at `(x, y)`, pointing towards `edge`
+fn fill_arrow<T: RenderTarget>(
+    target: &mut T,
+    x: f32,
+    y: f32,
+    edge: Edge,
+    color: Color,
+    clip: &Rect,
+) {
+    for row in 0..ARROW_DEPTH {
+        let depth = row as f32;
+        let half = depth + 1.0;
+        let (px, py, width, height) = match edge {
+            Edge::Top => (x - half, y + depth, half * 2.0, 1.0),
+            Edge::Bottom => (x - half, y - depth - 1.0, half * 2.0, 1.0),
+            Edge::Left => (x + depth, y - half, 1.0, half * 2.0),
+            Edge::Right => (x - depth - 1.0, y - half, 1.0, half * 2.0),
+        };
+        fill_rect_clipped(target, px, py, width, height, color, Some(clip));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::style::Size;
+    use image::{GrayImage, Luma};
+
+    /// Indicator of a 100px square container scrolled to `offset_y`
+    fn render(indicator: ScrollIndicator, offset_y: f32, content_height: f32) -> GrayImage {
+        let mut image = GrayImage::from_pixel(100, 100, Luma([255]));
+        let rect = Rect::new(0.0, 0.0, 100.0, 100.0);
+        let metrics = ScrollMetrics {
+            viewport: Size::new(100.0, 100.0),
+            content: Size::new(100.0, content_height),
+            offset_x: 0.0,
+            offset_y,
+            pages: None,
+        };
+        let fonts = Fonts::new();
+        render_scroll_indicator(
+            &mut image,
+            &fonts,
+            indicator,
+            Color::Black,
+            &rect,
+            &metrics,
+            &rect,
+        );
+        image
+    }
+
+    fn is_dark(image: &GrayImage, x: u32, y: u32) -> bool {
+        image.get_pixel(x, y).0[0] < 128
+    }
+
+    fn dark_pixels(image: &GrayImage) -> usize {
+        image.pixels().filter(|pixel| pixel.0[0] < 128).count()
+    }
+
+    #[test]
+    fn test_bar() {
+        // a quarter of the content is visible, so the thumb is 25px long
+        let image = render(ScrollIndicator::Bar, 0.0, 400.0);
+        assert!(is_dark(&image, 98, 0) && is_dark(&image, 98, 24));
+        assert!(!is_dark(&image, 98, 25));
+        assert!(!is_dark(&image, 95, 10));
+        assert_eq!(dark_pixels(&image), 4 * 25);
+
+        let image = render(ScrollIndicator::Bar, 300.0, 400.0);
+        assert!(is_dark(&image, 98, 99) && !is_dark(&image, 98, 74));
+    }
+
+    #[test]
+    fn test_page_label() {
+        let image = render(ScrollIndicator::PageLabel, 100.0, 400.0);
+        assert!(dark_pixels(&image) > 0);
+
+        // the label sits in the bottom-right corner and reads "page 2/4"
+        let fonts = Fonts::new();
+        let font = fonts.get(TextFont::default());
+        let numbers = measure_text_width(font, "2/4", LABEL_FONT_SIZE);
+        let top = 100 - font.as_scaled(LABEL_FONT_SIZE).height().ceil() as u32 - 8;
+        let dark: Vec<(u32, u32)> = image
+            .enumerate_pixels()
+            .filter(|(_, _, pixel)| pixel.0[0] < 128)
+            .map(|(x, y, _)| (x, y))
+            .collect();
+        assert!(dark.iter().all(|&(_, y)| y >= top));
+        let left = dark.iter().map(|&(x, _)| x).min().unwrap();
+        assert!((left as f32) < 100.0 - INSET * 3.0 - numbers * 1.5);
+    }
+
+    #[test]
+    fn test_arrows() {
+        // at the top only the bottom arrow shows
+        let image = render(ScrollIndicator::Arrows, 0.0, 400.0);
+        assert!(is_dark(&image, 50, 96));
+        assert!(!is_dark(&image, 50, 3));
+
+        let image = render(ScrollIndicator::Arrows, 100.0, 400.0);
+        assert!(is_dark(&image, 50, 3) && is_dark(&image, 50, 96));
+
+        let image = render(ScrollIndicator::Arrows, 300.0, 400.0);
+        assert!(is_dark(&image, 50, 3) && !is_dark(&image, 50, 96));
+    }
+
+    #[test]
+    fn test_nothing_drawn_without_overflow() {
+        assert_eq!(dark_pixels(&render(ScrollIndicator::None, 100.0, 400.0)), 0);
+        for indicator in [
+            ScrollIndicator::Bar,
+            ScrollIndicator::PageLabel,
+            ScrollIndicator::Arrows,
+        ] {
+            assert_eq!(dark_pixels(&render(indicator, 0.0, 100.0)), 0);
+        }
+    }
+}
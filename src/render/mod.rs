@@ -1,4 +1,6 @@
+mod focus;
 mod image;
+mod indicator;
 mod primitives;
 mod renderer;
 mod target;
@@ -1,285 +1,267 @@
 use image::{GrayImage, Luma};
 
+use crate::error::Result;
+use crate::focus::{FocusId, FocusState};
 use crate::font::Fonts;
-use crate::layout::{LayoutNode, LayoutTree, NodeData};
+use crate::layout::{LayoutTree, NodeData};
 use crate::nodes::{ListViewNode, Node, ScrollViewNode, ViewNode};
-use crate::style::{Rect, Size};
+use crate::style::{Color, FocusIndicator, Rect, Size};
 
+use super::focus::render_focus_indicator;
 use super::image::render_image;
+use super::indicator::render_scroll_indicator;
 use super::primitives::fill_rect_clipped;
 use super::target::RenderTarget;
 use super::text::render_text;
 
 pub struct Renderer {
     fonts: Fonts,
+    focus_indicator: FocusIndicator,
+    focus_color: Color,
+    focus_width: f32,
 }
 
 impl Renderer {
     pub fn new() -> Self {
         Self {
             fonts: Fonts::new(),
+            focus_indicator: FocusIndicator::Invert,
+            focus_color: Color::Black,
+            focus_width: 3.0,
         }
     }
 
-    pub fn render(&self, layout: &LayoutTree, root: &Node, size: Size) -> GrayImage {
+    /// How the focused node is marked, unless it sets its own indicator
+    pub fn focus_indicator(mut self, indicator: FocusIndicator) -> Self {
+        self.focus_indicator = indicator;
+        self
+    }
+
+    /// Color of focus borders and underlines
+    pub fn focus_color(mut self, color: Color) -> Self {
+        self.focus_color = color;
+        self
+    }
+
+    /// Thickness of focus borders and underlines
+    pub fn focus_width(mut self, px: f32) -> Self {
+        self.focus_width = px;
+        self
+    }
+
+    pub fn render(
+        &self,
+        layout: &LayoutTree,
+        root: &Node,
+        size: Size,
+        focus: Option<&FocusState>,
+    ) -> Result<GrayImage> {
         let mut image = GrayImage::from_pixel(size.width as u32, size.height as u32, Luma([255u8]));
-        self.render_to(&mut image, layout, root);
-        image
+        self.render_to(&mut image, layout, root, focus)?;
+        Ok(image)
     }
 
-    pub fn render_to<T: RenderTarget>(&self, target: &mut T, layout: &LayoutTree, root: &Node) {
-        self.render_node(target, root, layout, 0);
+    /// Render `root` using a layout computed for it, marking the node
+    /// focused in `focus`, if any.
+    ///
+    /// The layout is validated against the node tree first, so a stale or
+    /// mismatched layout returns an error instead of panicking mid-render.
+    pub fn render_to<T: RenderTarget>(
+        &self,
+        target: &mut T,
+        layout: &LayoutTree,
+        root: &Node,
+        focus: Option<&FocusState>,
+    ) -> Result<()> {
+        layout.validate(root)?;
+        let state = PaintState {
+            focused: focus.and_then(|focus| focus.focused_id),
+            ..Default::default()
+        };
+        self.render_node(target, root, layout, 0, state);
+        Ok(())
     }
 
+    /// Paint `node` and its subtree, returning the index just past it
     fn render_node<T: RenderTarget>(
         &self,
         target: &mut T,
         node: &Node,
         layout: &LayoutTree,
         index: usize,
+        state: PaintState,
     ) -> usize {
-        let layout_node = &layout.nodes[index];
+        if !node.is_painted() {
+            return index + node.subtree_len();
+        }
+
+        let layout_rect = layout.nodes[index].rect;
+        let rect = Rect {
+            x: layout_rect.x - state.scroll.0,
+            y: layout_rect.y - state.scroll.1,
+            ..layout_rect
+        };
+        let clip = state.clip.as_ref();
+        let focused = state.is_focused(node.focus_id());
 
-        match node {
-            Node::View(view) => self.render_view(target, view, layout_node, layout, index, None),
+        let next_index = match node {
+            Node::View(view) => self.render_view(target, view, &rect, layout, index, state),
             Node::Text(text) => {
-                render_text(target, &self.fonts, text, layout_node, None);
+                let color = match text.focused_color {
+                    Some(color) if focused => color,
+                    _ => text.color,
+                };
+                render_text(target, &self.fonts, text, color, &rect, clip);
                 index + 1
             }
             Node::Image(img) => {
-                render_image(target, img, layout_node, None);
+                render_image(target, img, &rect, clip);
                 index + 1
             }
             Node::ScrollView(scroll) => {
-                self.render_scroll_view(target, scroll, layout_node, layout, index)
+                self.render_scroll_view(target, scroll, &rect, layout, index, state)
             }
             Node::ListView(list) => {
-                self.render_list_view(target, list, layout_node, layout, index)
+                self.render_list_view(target, list, &rect, layout, index, state)
             }
-        }
-    }
-
-    fn render_view<T: RenderTarget>(
-        &self,
-        target: &mut T,
-        view: &ViewNode,
-        layout_node: &LayoutNode,
-        layout: &LayoutTree,
-        index: usize,
-        clip: Option<&Rect>,
-    ) -> usize {
-        let rect = &layout_node.rect;
-
-        if let Some(color) = view.background {
-            fill_rect_clipped(target, rect.x, rect.y, rect.width, rect.height, color, clip);
-        }
+        };
 
-        let mut next_index = index + 1;
-        for child in &view.children {
-            next_index = self.render_node_clipped(target, child, layout, next_index, clip);
+        // drawn over the children, so an inverted node inverts its content
+        if focused {
+            render_focus_indicator(
+                target,
+                node.focus_indicator().unwrap_or(self.focus_indicator),
+                self.focus_color,
+                self.focus_width,
+                &rect,
+                clip,
+            );
         }
 
         next_index
     }
 
-    fn render_scroll_view<T: RenderTarget>(
+    fn render_children<T: RenderTarget>(
         &self,
         target: &mut T,
-        scroll: &ScrollViewNode,
-        layout_node: &LayoutNode,
+        children: &[Node],
         layout: &LayoutTree,
         index: usize,
+        state: PaintState,
     ) -> usize {
-        let rect = &layout_node.rect;
-
-        let scroll_offset =
-            if let Some(NodeData::ScrollView { scroll_offset, .. }) = &layout_node.data {
-                *scroll_offset
-            } else {
-                scroll.scroll_offset
-            };
-
-        if let Some(color) = scroll.background {
-            fill_rect_clipped(target, rect.x, rect.y, rect.width, rect.height, color, None);
-        }
-
-        let clip_rect = Rect {
-            x: rect.x,
-            y: rect.y,
-            width: rect.width,
-            height: rect.height,
-        };
-
-        let mut next_index = index + 1;
-        for child in &scroll.children {
-            next_index = self.render_node_scrolled(
-                target,
-                child,
-                layout,
-                next_index,
-                &clip_rect,
-                scroll_offset,
-            );
+        let mut next_index = index;
+        for child in children {
+            next_index = self.render_node(target, child, layout, next_index, state);
         }
-
         next_index
     }
 
-    fn render_list_view<T: RenderTarget>(
+    fn render_view<T: RenderTarget>(
         &self,
         target: &mut T,
-        list: &ListViewNode,
-        layout_node: &LayoutNode,
+        view: &ViewNode,
+        rect: &Rect,
         layout: &LayoutTree,
         index: usize,
+        state: PaintState,
     ) -> usize {
-        let rect = &layout_node.rect;
-
-        let (scroll_offset, selected_index) =
-            if let Some(NodeData::ListView { scroll_offset, selected_index, .. }) = &layout_node.data {
-                (*scroll_offset, *selected_index)
-            } else {
-                (list.scroll_offset, list.selected_index)
-            };
-
-        if let Some(color) = list.background {
-            fill_rect_clipped(target, rect.x, rect.y, rect.width, rect.height, color, None);
-        }
-
-        let clip_rect = Rect {
-            x: rect.x,
-            y: rect.y,
-            width: rect.width,
-            height: rect.height,
+        let background = if state.is_focused(view.focus_id) {
+            view.focused_background.or(view.background)
+        } else {
+            view.background
         };
-
-        let mut next_index = index + 1;
-        for (child_idx, child) in list.children.iter().enumerate() {
-            let is_selected = selected_index == Some(child_idx);
-            
-            if is_selected {
-                if next_index < layout.nodes.len() {
-                    let child_layout = &layout.nodes[next_index];
-                    let adjusted_y = child_layout.rect.y - scroll_offset;
-                    
-                    if adjusted_y + child_layout.rect.height > rect.y && adjusted_y < rect.y + rect.height {
-                        fill_rect_clipped(
-                            target,
-                            child_layout.rect.x,
-                            adjusted_y,
-                            child_layout.rect.width,
-                            child_layout.rect.height,
-                            list.selected_background,
-                            Some(&clip_rect),
-                        );
-                    }
-                }
-            }
-            
-            next_index = self.render_node_scrolled(
+        if let Some(color) = background {
+            fill_rect_clipped(
                 target,
-                child,
-                layout,
-                next_index,
-                &clip_rect,
-                scroll_offset,
+                rect.x,
+                rect.y,
+                rect.width,
+                rect.height,
+                color,
+                state.clip.as_ref(),
             );
         }
 
-        next_index
-    }
-
-    fn render_node_clipped<T: RenderTarget>(
-        &self,
-        target: &mut T,
-        node: &Node,
-        layout: &LayoutTree,
-        index: usize,
-        clip: Option<&Rect>,
-    ) -> usize {
-        let layout_node = &layout.nodes[index];
-
-        match node {
-            Node::View(view) => self.render_view(target, view, layout_node, layout, index, clip),
-            Node::Text(text) => {
-                render_text(target, &self.fonts, text, layout_node, clip);
-                index + 1
-            }
-            Node::Image(img) => {
-                render_image(target, img, layout_node, clip);
-                index + 1
-            }
-            Node::ScrollView(scroll) => {
-                self.render_scroll_view(target, scroll, layout_node, layout, index)
-            }
-            Node::ListView(list) => {
-                self.render_list_view(target, list, layout_node, layout, index)
+        let child_state = if view.clip_children {
+            match state.clip_to(rect) {
+                Some(child_state) => child_state,
+                None => return skip_children(&view.children, index + 1),
             }
-        }
+        } else {
+            state
+        };
+
+        self.render_children(target, &view.children, layout, index + 1, child_state)
     }
 
-    fn render_node_scrolled<T: RenderTarget>(
+    fn render_scroll_view<T: RenderTarget>(
         &self,
         target: &mut T,
-        node: &Node,
+        scroll: &ScrollViewNode,
+        rect: &Rect,
         layout: &LayoutTree,
         index: usize,
-        clip: &Rect,
-        scroll_offset: f32,
+        state: PaintState,
     ) -> usize {
-        let layout_node = &layout.nodes[index];
+        let background = if state.is_focused(scroll.focus_id) {
+            scroll.focused_background.or(scroll.background)
+        } else {
+            scroll.background
+        };
+        if let Some(color) = background {
+            fill_rect_clipped(
+                target,
+                rect.x,
+                rect.y,
+                rect.width,
+                rect.height,
+                color,
+                state.clip.as_ref(),
+            );
+        }
 
-        let adjusted_rect = Rect {
-            x: layout_node.rect.x,
-            y: layout_node.rect.y - scroll_offset,
-            width: layout_node.rect.width,
-            height: layout_node.rect.height,
+        let Some(child_state) = state.clip_to(rect) else {
+            return skip_children(&scroll.children, index + 1);
         };
-        let adjusted_layout_node = LayoutNode {
-            rect: adjusted_rect,
-            data: layout_node.data.clone(),
+        let metrics = layout.scroll_metrics(index);
+        let child_state = match &metrics {
+            Some(metrics) => child_state.scrolled_by(metrics.offset_x, metrics.offset_y),
+            None => child_state.scrolled_by(scroll.scroll_offset_x, scroll.scroll_offset),
         };
 
-        match node {
-            Node::View(view) => self.render_view_scrolled(
+        let next_index =
+            self.render_children(target, &scroll.children, layout, index + 1, child_state);
+
+        if let (Some(metrics), Some(clip)) = (metrics, &child_state.clip) {
+            render_scroll_indicator(
                 target,
-                view,
-                &adjusted_layout_node,
-                layout,
-                index,
+                &self.fonts,
+                scroll.scroll_indicator,
+                scroll.indicator_color,
+                rect,
+                &metrics,
                 clip,
-                scroll_offset,
-            ),
-            Node::Text(text) => {
-                render_text(target, &self.fonts, text, &adjusted_layout_node, Some(clip));
-                index + 1
-            }
-            Node::Image(img) => {
-                render_image(target, img, &adjusted_layout_node, Some(clip));
-                index + 1
-            }
-            Node::ScrollView(scroll) => {
-                self.render_scroll_view(target, scroll, &adjusted_layout_node, layout, index)
-            }
-            Node::ListView(list) => {
-                self.render_list_view(target, list, &adjusted_layout_node, layout, index)
-            }
+            );
         }
+
+        next_index
     }
 
-    fn render_view_scrolled<T: RenderTarget>(
+    fn render_list_view<T: RenderTarget>(
         &self,
         target: &mut T,
-        view: &ViewNode,
-        layout_node: &LayoutNode,
+        list: &ListViewNode,
+        rect: &Rect,
         layout: &LayoutTree,
         index: usize,
-        clip: &Rect,
-        scroll_offset: f32,
+        state: PaintState,
     ) -> usize {
-        let rect = &layout_node.rect;
-
-        if let Some(color) = view.background {
+        let background = if state.is_focused(list.focus_id) {
+            list.focused_background.or(list.background)
+        } else {
+            list.background
+        };
+        if let Some(color) = background {
             fill_rect_clipped(
                 target,
                 rect.x,
@@ -287,22 +269,210 @@ impl Renderer {
                 rect.width,
                 rect.height,
                 color,
-                Some(clip),
+                state.clip.as_ref(),
             );
         }
 
+        let Some(child_state) = state.clip_to(rect) else {
+            return skip_children(&list.children, index + 1);
+        };
+        let (selected_index, child_state) = if let Some(NodeData::ListView {
+            scroll_offset,
+            scroll_offset_x,
+            selected_index,
+            ..
+        }) = &layout.nodes[index].data
+        {
+            (
+                *selected_index,
+                child_state.scrolled_by(*scroll_offset_x, *scroll_offset),
+            )
+        } else {
+            (
+                list.selected_index,
+                child_state.scrolled_by(list.scroll_offset_x, list.scroll_offset),
+            )
+        };
+        let selected_child = selected_index.and_then(|i| i.checked_sub(list.first_item()));
+        let child_clip = child_state.clip.as_ref();
+
         let mut next_index = index + 1;
-        for child in &view.children {
-            next_index =
-                self.render_node_scrolled(target, child, layout, next_index, clip, scroll_offset);
+        for (child_idx, child) in list.children.iter().enumerate() {
+            if selected_child == Some(child_idx) && child.is_painted() {
+                let child_rect = &layout.nodes[next_index].rect;
+                fill_rect_clipped(
+                    target,
+                    child_rect.x - child_state.scroll.0,
+                    child_rect.y - child_state.scroll.1,
+                    child_rect.width,
+                    child_rect.height,
+                    list.selected_background,
+                    child_clip,
+                );
+            }
+
+            next_index = self.render_node(target, child, layout, next_index, child_state);
+        }
+
+        if let (Some(metrics), Some(clip)) = (layout.scroll_metrics(index), child_clip) {
+            render_scroll_indicator(
+                target,
+                &self.fonts,
+                list.scroll_indicator,
+                list.indicator_color,
+                rect,
+                &metrics,
+                clip,
+            );
         }
 
         next_index
     }
 }
 
+/// Scroll offset and clip a node inherits from its ancestors
+#[derive(Debug, Clone, Copy, Default)]
+struct PaintState {
+    /// Sum of the offsets of all enclosing scroll containers
+    scroll: (f32, f32),
+    /// Intersection of the rects of all clipping ancestors, on screen;
+    /// `None` if nothing above clips
+    clip: Option<Rect>,
+    focused: Option<FocusId>,
+}
+
+impl PaintState {
+    /// Narrow the clip to `rect`, or `None` if nothing would remain visible
+    fn clip_to(self, rect: &Rect) -> Option<Self> {
+        let clip = match &self.clip {
+            Some(clip) => clip.intersect(rect)?,
+            None => *rect,
+        };
+        Some(Self {
+            clip: Some(clip),
+            ..self
+        })
+    }
+
+    fn is_focused(&self, id: Option<FocusId>) -> bool {
+        id.is_some() && id == self.focused
+    }
+
+    fn scrolled_by(self, x: f32, y: f32) -> Self {
+        Self {
+            scroll: (self.scroll.0 + x, self.scroll.1 + y),
+            ..self
+        }
+    }
+}
+
+/// Index just past `children`, for subtrees that are clipped away entirely
+fn skip_children(children: &[Node], index: usize) -> usize {
+    index + children.iter().map(Node::subtree_len).sum::<usize>()
+}
+
 impl Default for Renderer {
     fn default() -> Self {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout::LayoutEngine;
+    use crate::nodes::{ScrollView, View};
+    use crate::style::{Color, Dimension};
+    use crate::test_util::block;
+
+    fn render(root: &Node) -> GrayImage {
+        let size = Size::new(200.0, 200.0);
+        let layout = LayoutEngine::new().compute(root, size).unwrap();
+        Renderer::new().render(&layout, root, size, None).unwrap()
+    }
+
+    #[test]
+    fn test_nested_scroll_view_is_clipped_by_ancestor() {
+        let root = View::column()
+            .width(Dimension::Percent(1.0))
+            .child(
+                ScrollView::vertical().height(Dimension::Px(50.0)).child(
+                    ScrollView::vertical()
+                        .height(Dimension::Px(100.0))
+                        .background(Color::Black),
+                ),
+            )
+            .build();
+        let image = render(&root);
+
+        assert_eq!(image.get_pixel(10, 40).0[0], 0);
+        assert_eq!(image.get_pixel(10, 60).0[0], 255);
+    }
+
+    #[test]
+    fn test_clip_children() {
+        let card = |clip| {
+            View::column().child(
+                // a row lets the taller child overflow instead of shrinking
+                View::row()
+                    .height(Dimension::Px(50.0))
+                    .clip_children(clip)
+                    .child(block(100.0).background(Color::Black)),
+            )
+        };
+
+        assert_eq!(render(&card(false).build()).get_pixel(10, 60).0[0], 0);
+        assert_eq!(render(&card(true).build()).get_pixel(10, 60).0[0], 255);
+        assert_eq!(render(&card(true).build()).get_pixel(10, 40).0[0], 0);
+    }
+
+    fn render_focused(renderer: Renderer, root: &Node, id: FocusId) -> GrayImage {
+        let size = Size::new(200.0, 200.0);
+        let layout = LayoutEngine::new().compute(root, size).unwrap();
+        let mut focus = FocusState::new();
+        focus.update_from_layout(&layout);
+        focus.set_focus(id);
+        renderer.render(&layout, root, size, Some(&focus)).unwrap()
+    }
+
+    #[test]
+    fn test_focus_indicators() {
+        let root = View::column()
+            .width(Dimension::Percent(1.0))
+            .child(block(50.0).focusable(1))
+            .child(block(50.0).focusable(2))
+            .build();
+
+        let image = render_focused(Renderer::new(), &root, 2);
+        assert_eq!(image.get_pixel(10, 10).0[0], 255);
+        assert_eq!(image.get_pixel(10, 60).0[0], 0);
+
+        let renderer = Renderer::new().focus_indicator(FocusIndicator::Border);
+        let image = render_focused(renderer, &root, 2);
+        assert_eq!(image.get_pixel(1, 75).0[0], 0);
+        assert_eq!(image.get_pixel(50, 75).0[0], 255);
+
+        let renderer = Renderer::new().focus_indicator(FocusIndicator::Underline);
+        let image = render_focused(renderer, &root, 2);
+        assert_eq!(image.get_pixel(50, 98).0[0], 0);
+        assert_eq!(image.get_pixel(50, 52).0[0], 255);
+    }
+
+    #[test]
+    fn test_focused_background() {
+        let root = View::column()
+            .width(Dimension::Percent(1.0))
+            .child(
+                block(50.0)
+                    .focusable(1)
+                    .background(Color::White)
+                    .focused_background(Color::Black)
+                    .focus_indicator(FocusIndicator::None),
+            )
+            .build();
+
+        assert_eq!(render(&root).get_pixel(10, 10).0[0], 255);
+        let image = render_focused(Renderer::new(), &root, 1);
+        assert_eq!(image.get_pixel(10, 10).0[0], 0);
+    }
+}
@@ -1,9 +1,8 @@
 use ab_glyph::{point, Font, FontRef, ScaleFont};
 
 use crate::font::Fonts;
-use crate::layout::LayoutNode;
 use crate::nodes::TextNode;
-use crate::style::{Rect, TextAlign, TextOverflow};
+use crate::style::{Color, Rect, TextAlign, TextOverflow};
 use crate::text::wrap_text;
 
 use super::primitives::is_within_clip;
@@ -14,15 +13,15 @@ pub fn render_text<T: RenderTarget>(
     target: &mut T,
     fonts: &Fonts,
     text: &TextNode,
-    layout_node: &LayoutNode,
+    color: Color,
+    rect: &Rect,
     clip: Option<&Rect>,
 ) {
     let font = fonts.get(text.font);
-    let rect = &layout_node.rect;
     let scaled_font = font.as_scaled(text.font_size);
     let line_height = scaled_font.height();
     let ascent = scaled_font.ascent();
-    let luma = text.color.to_luma();
+    let luma = color.to_luma();
 
     let lines = wrap_text(font, &text.content, text.font_size, rect.width, text.wrap);
 
@@ -83,7 +82,7 @@ fn measure_line(font: &FontRef<'static>, text: &str, font_size: f32) -> f32 {
     width
 }
 
-fn render_line_clipped<T: RenderTarget>(
+pub(super) fn render_line_clipped<T: RenderTarget>(
     target: &mut T,
     font: &FontRef<'static>,
     text: &str,
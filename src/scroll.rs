@@ -0,0 +1,214 @@
+use crate::layout::{LayoutTree, ScrollMetrics};
+
+/// Scroll position of a single `ScrollView` or `ListView`.
+///
+/// The app keeps one of these per scroll container, passes `offset_x` and
+/// `offset_y` to the node builder each frame, and moves it with the
+/// `ScrollMetrics` of the previous layout so it never runs past the content.
+/// Layout can move the offset itself, e.g. when a `ListView` auto-scrolls to
+/// its selection, so call `sync` with the new metrics after each layout.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ScrollController {
+    pub offset_x: f32,
+    pub offset_y: f32,
+}
+
+impl ScrollController {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Take over the offsets a layout actually used
+    pub fn sync(&mut self, metrics: &ScrollMetrics) {
+        self.offset_x = metrics.offset_x;
+        self.offset_y = metrics.offset_y;
+    }
+
+    pub fn clamp(&mut self, metrics: &ScrollMetrics) {
+        self.offset_x = metrics.clamp_x(self.offset_x);
+        self.offset_y = metrics.clamp_y(self.offset_y);
+    }
+
+    pub fn scroll_to(&mut self, x: f32, y: f32, metrics: &ScrollMetrics) {
+        self.offset_x = metrics.clamp_x(x);
+        self.offset_y = metrics.clamp_y(y);
+    }
+
+    pub fn scroll_by(&mut self, dx: f32, dy: f32, metrics: &ScrollMetrics) {
+        self.scroll_to(self.offset_x + dx, self.offset_y + dy, metrics);
+    }
+
+    /// Scroll by whole viewports; negative `pages` scroll back.
+    /// Scrolls vertically unless only the width overflows.
+    pub fn scroll_by_page(&mut self, pages: i32, metrics: &ScrollMetrics) {
+        if metrics.is_horizontal() {
+            self.scroll_by(pages as f32 * metrics.viewport.width, 0.0, metrics);
+        } else {
+            self.scroll_by(0.0, pages as f32 * metrics.viewport.height, metrics);
+        }
+    }
+
+    pub fn is_at_start(&self) -> bool {
+        self.offset_x <= 0.0 && self.offset_y <= 0.0
+    }
+
+    pub fn is_at_end(&self, metrics: &ScrollMetrics) -> bool {
+        self.offset_x >= metrics.max_offset_x() && self.offset_y >= metrics.max_offset_y()
+    }
+
+    /// Scroll the container at `container` so its `child`-th child starts at
+    /// the top-left of the viewport, as far as the content allows.
+    /// Returns false if there is no such container or child.
+    pub fn scroll_to_child(&mut self, layout: &LayoutTree, container: usize, child: usize) -> bool {
+        match layout.children(container).get(child) {
+            Some(&index) => self.scroll_to_index(layout, container, index),
+            None => false,
+        }
+    }
+
+    /// Like `scroll_to_child`, for the keyed node anywhere inside the container
+    pub fn scroll_to_key(&mut self, layout: &LayoutTree, container: usize, key: &str) -> bool {
+        match layout.index_of(key) {
+            Some(index) => self.scroll_to_index(layout, container, index),
+            None => false,
+        }
+    }
+
+    fn scroll_to_index(&mut self, layout: &LayoutTree, container: usize, index: usize) -> bool {
+        let Some(metrics) = layout.scroll_metrics(container) else {
+            return false;
+        };
+        if index == container || !layout.path_to(index).contains(&container) {
+            return false;
+        }
+
+        let container_rect = layout.nodes[container].rect;
+        let rect = layout.nodes[index].rect;
+        self.scroll_to(
+            rect.x - container_rect.x,
+            rect.y - container_rect.y,
+            &metrics,
+        );
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout::LayoutEngine;
+    use crate::nodes::{ListView, View};
+    use crate::style::{Dimension, ScrollAlign, Size};
+
+    fn list_layout() -> LayoutTree {
+        let root = ListView::vertical()
+            .height(Dimension::Px(100.0))
+            .padding(10.0)
+            .children((0..10).map(|i| {
+                View::new()
+                    .key(format!("item-{}", i))
+                    .height(Dimension::Px(50.0))
+            }))
+            .build();
+        LayoutEngine::new()
+            .compute(&root, Size::new(200.0, 400.0))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_scroll_metrics() {
+        let layout = list_layout();
+        let metrics = layout.scroll_metrics(0).unwrap();
+
+        assert_eq!(metrics.viewport.height, 100.0);
+        assert_eq!(metrics.content.height, 520.0);
+        assert_eq!(metrics.max_offset_y(), 420.0);
+        assert!(layout.scroll_metrics(1).is_none());
+    }
+
+    #[test]
+    fn test_scroll_is_clamped() {
+        let metrics = list_layout().scroll_metrics(0).unwrap();
+        let mut scroll = ScrollController::new();
+
+        scroll.scroll_by(0.0, -30.0, &metrics);
+        assert_eq!(scroll.offset_y, 0.0);
+        assert!(scroll.is_at_start());
+
+        scroll.scroll_by(0.0, 1000.0, &metrics);
+        assert_eq!(scroll.offset_y, 420.0);
+        assert!(scroll.is_at_end(&metrics));
+    }
+
+    #[test]
+    fn test_scroll_by_page() {
+        let metrics = list_layout().scroll_metrics(0).unwrap();
+        let mut scroll = ScrollController::new();
+
+        scroll.scroll_by_page(1, &metrics);
+        assert_eq!(scroll.offset_y, 100.0);
+
+        scroll.scroll_by_page(-2, &metrics);
+        assert_eq!(scroll.offset_y, 0.0);
+    }
+
+    #[test]
+    fn test_page_numbers() {
+        let mut metrics = list_layout().scroll_metrics(0).unwrap();
+        assert_eq!(metrics.page_count(), 6);
+        assert_eq!(metrics.current_page(), 0);
+
+        metrics.offset_y = 200.0;
+        assert_eq!(metrics.current_page(), 2);
+
+        // the last page is shorter than the viewport
+        metrics.offset_y = metrics.max_offset_y();
+        assert_eq!(metrics.current_page(), 5);
+    }
+
+    #[test]
+    fn test_scroll_to_child() {
+        let layout = list_layout();
+        let mut scroll = ScrollController::new();
+
+        assert!(scroll.scroll_to_child(&layout, 0, 2));
+        assert_eq!(scroll.offset_y, 110.0);
+
+        assert!(scroll.scroll_to_key(&layout, 0, "item-9"));
+        assert_eq!(scroll.offset_y, 420.0);
+
+        assert!(!scroll.scroll_to_child(&layout, 0, 10));
+        assert!(!scroll.scroll_to_key(&layout, 0, "missing"));
+    }
+
+    #[test]
+    fn test_sync_keeps_nearest_auto_scroll_stable() {
+        let mut engine = LayoutEngine::new();
+        let mut scroll = ScrollController::new();
+        let mut frame = |selected: usize, scroll: &mut ScrollController| {
+            let root = ListView::vertical()
+                .height(Dimension::Px(100.0))
+                .scroll_align(ScrollAlign::Nearest)
+                .scroll_offset(scroll.offset_y)
+                .selected_index(Some(selected))
+                .children((0..10).map(|_| View::new().height(Dimension::Px(40.0))))
+                .build();
+            let layout = engine.compute(&root, Size::new(200.0, 400.0)).unwrap();
+            let metrics = layout.scroll_metrics(0).unwrap();
+            scroll.sync(&metrics);
+            metrics
+        };
+
+        // item 5 spans 200..240, so its bottom edge lines up with the viewport's
+        frame(5, &mut scroll);
+        assert_eq!(scroll.offset_y, 140.0);
+
+        // item 4 is already visible, so the list stays put
+        let metrics = frame(4, &mut scroll);
+        assert_eq!(scroll.offset_y, 140.0);
+
+        // paging starts from where auto-scroll left the list
+        scroll.scroll_by_page(1, &metrics);
+        assert_eq!(scroll.offset_y, 240.0);
+    }
+}
@@ -0,0 +1,12 @@
+// how the renderer marks the focused node
+// invert -> flip every pixel of the node, after its content is painted
+// border -> solid frame along the inside of the node's edges
+// underline -> solid bar along the node's bottom edge
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum FocusIndicator {
+    #[default]
+    Invert,
+    Border,
+    Underline,
+    None,
+}
@@ -1,11 +1,17 @@
 mod color;
 mod dimension;
 mod flex;
+mod focus;
 mod image;
+mod scroll;
 mod text;
+mod visibility;
 
 pub use color::Color;
 pub use dimension::{Dimension, Rect, Size};
 pub use flex::{Align, FlexDirection, Justify};
+pub use focus::FocusIndicator;
 pub use image::ImageFit;
+pub use scroll::{ScrollAlign, ScrollIndicator};
 pub use text::{TextAlign, TextFont, TextOverflow, TextWrap};
+pub use visibility::{Display, Visibility};
@@ -0,0 +1,23 @@
+// where a scroll container places a child it scrolls into view
+// nearest -> scroll as little as possible to make the child fully visible
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ScrollAlign {
+    #[default]
+    Nearest,
+    Start,
+    Center,
+    End,
+}
+
+// how a scroll container shows that it has more content
+// bar -> thin solid thumb along the trailing edge
+// page label -> "page 3/7" in the bottom-right corner
+// arrows -> triangles at the edges the content can still scroll towards
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ScrollIndicator {
+    #[default]
+    None,
+    Bar,
+    PageLabel,
+    Arrows,
+}
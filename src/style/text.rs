@@ -1,3 +1,9 @@
+// how text breaks into lines
+// none -> one line, newlines included
+// word -> between words, collapsing whitespace and newlines
+// char -> between any two chars, newlines included
+// word_char -> between words, and inside words wider than a line
+// TextPaginator also starts a new line at each newline, except with none
 #[derive(Debug, Clone, Copy, PartialEq, Default)]
 pub enum TextWrap {
     #[default]
@@ -22,7 +28,7 @@ pub enum TextAlign {
     Right,
 }
 
-#[derive(Debug, Clone, Copy, Default)]
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
 pub enum TextFont {
     #[default]
     NotosansRegular,
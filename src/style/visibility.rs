@@ -0,0 +1,17 @@
+// whether the node takes part in layout
+// none -> no space is reserved, the node and its children are not painted
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Display {
+    #[default]
+    Flex,
+    None,
+}
+
+// whether the node is painted
+// invisible -> space is still reserved in the layout
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Visibility {
+    #[default]
+    Visible,
+    Invisible,
+}
@@ -0,0 +1,9 @@
+use crate::nodes::View;
+use crate::style::Dimension;
+
+/// A 100px wide view of the given height
+pub(crate) fn block(height: f32) -> View {
+    View::new()
+        .width(Dimension::Px(100.0))
+        .height(Dimension::Px(height))
+}
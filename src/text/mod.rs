@@ -3,5 +3,6 @@ mod paginate;
 mod wrap;
 
 pub use measure::{line_height, measure_text_width};
-pub use paginate::TextPaginator;
+pub use paginate::{TextPage, TextPaginator};
 pub use wrap::wrap_text;
+pub(crate) use wrap::WrapLines;
@@ -1,13 +1,51 @@
+use std::collections::VecDeque;
+use std::ops::Range;
 
 use ab_glyph::FontRef;
 
 use super::measure::line_height;
-use super::wrap::wrap_text;
+use super::wrap::WrapLines;
 use crate::style::TextWrap;
 
+/// One page of a `TextPaginator`
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextPage {
+    /// Byte range of the page in the paginated content
+    pub range: Range<usize>,
+    /// Byte ranges of the page's lines
+    pub lines: Vec<Range<usize>>,
+}
+
+#[derive(Debug, Clone)]
+struct Line {
+    range: Range<usize>,
+    /// First line after a newline in the content, or the first line of all
+    paragraph_start: bool,
+}
+
+/// Splits text into pages of whole lines.
+///
+/// Pages keep their byte range in the content, so a reading position can be
+/// stored as a byte offset and found again with `page_of_offset` after the
+/// font or page size changes. `lazy` paginators only wrap as much of the
+/// content as the pages asked for so far need.
 #[derive(Debug, Clone)]
 pub struct TextPaginator {
-    pages: Vec<String>,
+    content: String,
+    font: FontRef<'static>,
+    font_size: f32,
+    available_width: f32,
+    wrap: TextWrap,
+    lines_per_page: usize,
+    widows: usize,
+    orphans: usize,
+    lazy: bool,
+    pages: Vec<TextPage>,
+    /// Wrapped lines not yet on a page
+    pending: VecDeque<Line>,
+    /// Where wrapping continues once `pending` runs out
+    wrap_offset: usize,
+    complete: bool,
 }
 
 impl TextPaginator {
@@ -19,69 +57,287 @@ impl TextPaginator {
         available_height: f32,
         wrap: TextWrap,
     ) -> Self {
-        let pages = paginate_text(font, content, font_size, available_width, available_height, wrap);
-        Self { pages }
+        let mut paginator = Self::lazy(
+            font,
+            content,
+            font_size,
+            available_width,
+            available_height,
+            wrap,
+        );
+        paginator.lazy = false;
+        paginator.paginate_all();
+        paginator
+    }
+
+    /// A paginator that doesn't paginate anything until pages are asked for
+    pub fn lazy(
+        font: &FontRef<'static>,
+        content: &str,
+        font_size: f32,
+        available_width: f32,
+        available_height: f32,
+        wrap: TextWrap,
+    ) -> Self {
+        // a page always takes at least one line, even if it's cut off
+        let lines_per_page =
+            ((available_height / line_height(font, font_size)).floor() as usize).max(1);
+
+        Self {
+            content: content.to_string(),
+            font: font.clone(),
+            font_size,
+            available_width,
+            wrap,
+            lines_per_page,
+            widows: 1,
+            orphans: 1,
+            lazy: true,
+            pages: Vec::new(),
+            pending: VecDeque::new(),
+            wrap_offset: 0,
+            complete: false,
+        }
+    }
+
+    /// Minimum number of lines of a paragraph at the top of a page
+    pub fn widows(mut self, lines: usize) -> Self {
+        self.widows = lines.max(1);
+        self.restart();
+        self
     }
 
+    /// Minimum number of lines of a paragraph at the bottom of a page
+    pub fn orphans(mut self, lines: usize) -> Self {
+        self.orphans = lines.max(1);
+        self.restart();
+        self
+    }
+
+    /// Number of pages paginated so far; all of them unless lazy
     pub fn page_count(&self) -> usize {
         self.pages.len()
     }
 
+    /// True once the whole content has been paginated
+    pub fn is_complete(&self) -> bool {
+        self.complete
+    }
+
     pub fn get_page(&self, index: usize) -> Option<&str> {
-        self.pages.get(index).map(|s| s.as_str())
+        self.pages
+            .get(index)
+            .map(|page| &self.content[page.range.clone()])
     }
 
-    pub fn pages(&self) -> &[String] {
+    pub fn page(&self, index: usize) -> Option<&TextPage> {
+        self.pages.get(index)
+    }
+
+    pub fn pages(&self) -> &[TextPage] {
         &self.pages
     }
 
+    pub fn content(&self) -> &str {
+        &self.content
+    }
+
     pub fn is_empty(&self) -> bool {
         self.pages.is_empty()
     }
-}
 
-fn paginate_text(
-    font: &FontRef<'static>,
-    content: &str,
-    font_size: f32,
-    available_width: f32,
-    available_height: f32,
-    wrap: TextWrap,
-) -> Vec<String> {
-    let lines = wrap_text(font, content, font_size, available_width, wrap);
-    
-    if lines.is_empty() {
-        return vec![String::new()];
+    /// Paginate up to and including page `index`; false if there is no
+    /// such page
+    pub fn paginate_to(&mut self, index: usize) -> bool {
+        while self.pages.len() <= index && self.paginate_next() {}
+        index < self.pages.len()
+    }
+
+    pub fn paginate_all(&mut self) {
+        while self.paginate_next() {}
+    }
+
+    /// Index of the page showing the byte at `offset`, paginating as far as
+    /// needed. Offsets in the whitespace between pages belong to the next.
+    pub fn page_of_offset(&mut self, offset: usize) -> usize {
+        // pages are in content order, so only new pages need checking
+        let index = self.pages.partition_point(|page| page.range.end <= offset);
+        if index < self.pages.len() {
+            return index;
+        }
+        while self.paginate_next() {
+            let last = self.pages.len() - 1;
+            if offset < self.pages[last].range.end {
+                return last;
+            }
+        }
+        self.pages.len().saturating_sub(1)
     }
 
-    let line_h = line_height(font, font_size);
-    let lines_per_page = (available_height / line_h).floor() as usize;
-    
-    if lines_per_page == 0 {
-        return vec![content.to_string()];
+    fn restart(&mut self) {
+        self.pages.clear();
+        self.pending.clear();
+        self.wrap_offset = 0;
+        self.complete = false;
+        if !self.lazy {
+            self.paginate_all();
+        }
     }
 
-    let mut pages = Vec::new();
-    let mut current_page_lines: Vec<&str> = Vec::new();
+    /// Wrap lines until `count` are pending or the content runs out
+    fn fill(&mut self, count: usize) {
+        if self.pending.len() >= count {
+            return;
+        }
 
-    for line in &lines {
-        current_page_lines.push(line);
-        
-        if current_page_lines.len() >= lines_per_page {
-            pages.push(current_page_lines.join("\n"));
-            current_page_lines.clear();
+        let mut lines = ParagraphLines {
+            font: &self.font,
+            text: &self.content,
+            font_size: self.font_size,
+            max_width: self.available_width,
+            wrap: self.wrap,
+            offset: self.wrap_offset,
+        };
+        while self.pending.len() < count {
+            let Some(range) = lines.next() else {
+                break;
+            };
+            let paragraph_start = match self.pending.back() {
+                Some(previous) => self.content[previous.range.end..range.start].contains('\n'),
+                None => match self.pages.last() {
+                    Some(page) => self.content[page.range.end..range.start].contains('\n'),
+                    None => true,
+                },
+            };
+            self.pending.push_back(Line {
+                range,
+                paragraph_start,
+            });
         }
+        self.wrap_offset = lines.offset;
     }
 
-    if !current_page_lines.is_empty() {
-        pages.push(current_page_lines.join("\n"));
+    fn paginate_next(&mut self) -> bool {
+        if self.complete {
+            return false;
+        }
+
+        self.fill(self.lines_per_page + self.widows);
+        if self.pending.is_empty() {
+            self.complete = true;
+            return false;
+        }
+
+        let end = self.page_break();
+        let lines: Vec<Range<usize>> = self.pending.drain(..end).map(|line| line.range).collect();
+        let range = lines[0].start..lines[lines.len() - 1].end;
+        self.pages.push(TextPage { range, lines });
+
+        if self.pending.is_empty() {
+            self.fill(1);
+            self.complete = self.pending.is_empty();
+        }
+        true
     }
 
-    if pages.is_empty() {
-        pages.push(String::new());
+    /// Number of pending lines that go on the next page
+    fn page_break(&self) -> usize {
+        let mut end = self.lines_per_page.min(self.pending.len());
+        if end == self.pending.len() || self.pending[end].paragraph_start {
+            return end;
+        }
+
+        // the page breaks inside a paragraph; `start` is where that
+        // paragraph begins on this page, if it does
+        let start = (0..end).rev().find(|&i| self.pending[i].paragraph_start);
+        let first = start.unwrap_or(0);
+        let after = self
+            .pending
+            .iter()
+            .skip(end)
+            .take_while(|line| !line.paragraph_start)
+            .count();
+
+        if end - first < self.orphans {
+            // too few lines at the bottom: move the paragraph to the next page
+            if let Some(start) = start.filter(|&start| start > 0) {
+                end = start;
+            }
+        } else if after < self.widows {
+            // too few lines at the top of the next page: move some down
+            let moved = end
+                .checked_sub(self.widows - after)
+                .filter(|&moved| moved > first)
+                .filter(|&moved| start.is_none() || moved - first >= self.orphans);
+            if let Some(moved) = moved {
+                end = moved;
+            } else if let Some(start) = start.filter(|&start| start > 0) {
+                end = start;
+            }
+        }
+
+        end
     }
+}
 
-    pages
+/// Byte ranges of the lines of `text` from `offset` on, wrapped like
+/// `wrap_text` except that, unless with `TextWrap::None`, a newline always
+/// ends a line.
+///
+/// Each newline after it adds an empty line; a newline at the very end of
+/// the text doesn't. Each line only depends on where it starts, so wrapping
+/// can stop after any line and resume later from `offset`.
+#[derive(Debug, Clone)]
+struct ParagraphLines<'a> {
+    font: &'a FontRef<'static>,
+    text: &'a str,
+    font_size: f32,
+    max_width: f32,
+    wrap: TextWrap,
+    offset: usize,
+}
+
+impl Iterator for ParagraphLines<'_> {
+    type Item = Range<usize>;
+
+    fn next(&mut self) -> Option<Range<usize>> {
+        // empty text still has one empty line
+        let done = self.offset > self.text.len()
+            || (self.offset == self.text.len() && !self.text.is_empty());
+        if done {
+            return None;
+        }
+
+        let end = match self.wrap {
+            TextWrap::None => self.text.len(),
+            _ => self.text[self.offset..]
+                .find('\n')
+                .map_or(self.text.len(), |len| self.offset + len),
+        };
+        // each paragraph wraps on its own; an empty one is an empty line
+        let paragraph = &self.text[..end];
+        let line = WrapLines::new(
+            self.font,
+            paragraph,
+            self.font_size,
+            self.max_width,
+            self.wrap,
+            self.offset,
+        )
+        .next()
+        .unwrap_or(self.offset..self.offset);
+
+        // word wrapping drops the whitespace at the end of a paragraph
+        let rest_is_blank = matches!(self.wrap, TextWrap::Word | TextWrap::WordChar)
+            && paragraph[line.end..].trim().is_empty();
+        self.offset = if line.end == end || rest_is_blank {
+            // past the newline
+            end + 1
+        } else {
+            line.end
+        };
+        Some(line)
+    }
 }
 
 #[cfg(test)]
@@ -89,22 +345,94 @@ mod tests {
     use super::*;
     use crate::font::Fonts;
     use crate::style::TextFont;
+    use crate::text::measure_text_width;
+
+    /// Three lines per page, narrow enough for one word per line
+    fn one_word_lines(content: &str) -> TextPaginator {
+        let fonts = Fonts::new();
+        let font = fonts.get(TextFont::NotosansRegular);
+        let height = line_height(font, 24.0) * 3.0 + 1.0;
+        let width = measure_text_width(font, "m", 24.0) + 1.0;
+        TextPaginator::new(font, content, 24.0, width, height, TextWrap::Word)
+    }
+
+    fn paragraph_lines(text: &str, wrap: TextWrap) -> Vec<&str> {
+        let fonts = Fonts::new();
+        let lines = ParagraphLines {
+            font: fonts.get(TextFont::NotosansRegular),
+            text,
+            font_size: 24.0,
+            max_width: 400.0,
+            wrap,
+            offset: 0,
+        };
+        lines.map(|range| &text[range]).collect()
+    }
+
+    fn page_lines(paginator: &TextPaginator) -> Vec<usize> {
+        paginator
+            .pages()
+            .iter()
+            .map(|page| page.lines.len())
+            .collect()
+    }
+
+    #[test]
+    fn test_newline_ends_a_line() {
+        assert_eq!(
+            paragraph_lines("one\ntwo  three", TextWrap::Word),
+            ["one", "two  three"]
+        );
+        assert_eq!(paragraph_lines("one\ntwo", TextWrap::Char), ["one", "two"]);
+        assert_eq!(
+            paragraph_lines("one\ntwo", TextWrap::WordChar),
+            ["one", "two"]
+        );
+        // no wrapping keeps the text as it is
+        assert_eq!(paragraph_lines("one\ntwo", TextWrap::None), ["one\ntwo"]);
+    }
+
+    #[test]
+    fn test_blank_lines_are_kept() {
+        assert_eq!(paragraph_lines("\n\n", TextWrap::Word), ["", ""]);
+        assert_eq!(paragraph_lines("a\n\nb", TextWrap::Word), ["a", "", "b"]);
+        assert_eq!(
+            paragraph_lines("a\n  \nb\n", TextWrap::Word),
+            ["a", "", "b"]
+        );
+        assert_eq!(paragraph_lines("a\n\nb", TextWrap::Char), ["a", "", "b"]);
+        assert_eq!(paragraph_lines("", TextWrap::Word), [""]);
+    }
+
+    #[test]
+    fn test_resume_from_offset() {
+        let fonts = Fonts::new();
+        let text = "a\n\nb c";
+        let mut lines = ParagraphLines {
+            font: fonts.get(TextFont::NotosansRegular),
+            text,
+            font_size: 24.0,
+            max_width: 400.0,
+            wrap: TextWrap::Word,
+            offset: 0,
+        };
+        assert_eq!(lines.next(), Some(0..1));
+
+        let resumed = ParagraphLines {
+            offset: lines.offset,
+            ..lines
+        };
+        assert_eq!(resumed.collect::<Vec<_>>(), [2..2, 3..6]);
+    }
 
     #[test]
     fn test_paginator_basic() {
         let fonts = Fonts::new();
         let font = fonts.get(TextFont::NotosansRegular);
-        
+
         let content = "Line 1\nLine 2\nLine 3\nLine 4\nLine 5";
-        let paginator = TextPaginator::new(
-            font,
-            content,
-            24.0,
-            400.0,
-            50.0, 
-            TextWrap::Word,
-        );
-        
+        let paginator = TextPaginator::new(font, content, 24.0, 400.0, 50.0, TextWrap::Word);
+
         assert!(paginator.page_count() > 0);
     }
 
@@ -112,17 +440,87 @@ mod tests {
     fn test_paginator_empty_content() {
         let fonts = Fonts::new();
         let font = fonts.get(TextFont::NotosansRegular);
-        
-        let paginator = TextPaginator::new(
-            font,
-            "",
-            24.0,
-            400.0,
-            400.0,
-            TextWrap::Word,
-        );
-        
+
+        let paginator = TextPaginator::new(font, "", 24.0, 400.0, 400.0, TextWrap::Word);
+
         assert_eq!(paginator.page_count(), 1);
         assert_eq!(paginator.get_page(0), Some(""));
     }
+
+    #[test]
+    fn test_pages_map_back_to_content() {
+        let fonts = Fonts::new();
+        let font = fonts.get(TextFont::NotosansRegular);
+        let content = "alpha beta gamma delta epsilon zeta eta theta iota kappa";
+        let paginator = TextPaginator::new(font, content, 24.0, 120.0, 60.0, TextWrap::Word);
+
+        assert!(paginator.page_count() > 1);
+        for (index, page) in paginator.pages().iter().enumerate() {
+            assert_eq!(
+                paginator.get_page(index),
+                Some(&content[page.range.clone()])
+            );
+        }
+        let words: Vec<&str> = (0..paginator.page_count())
+            .flat_map(|i| paginator.get_page(i).unwrap().split_whitespace())
+            .collect();
+        assert_eq!(words, content.split_whitespace().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_page_too_short_for_a_line() {
+        let fonts = Fonts::new();
+        let font = fonts.get(TextFont::NotosansRegular);
+
+        // every page still takes one line
+        let paginator = TextPaginator::new(font, "a\nb\nc", 24.0, 400.0, 1.0, TextWrap::Word);
+        assert_eq!(paginator.page_count(), 3);
+        assert_eq!(paginator.get_page(1), Some("b"));
+    }
+
+    #[test]
+    fn test_lazy_pagination_and_offsets() {
+        let fonts = Fonts::new();
+        let font = fonts.get(TextFont::NotosansRegular);
+        let content = "x".repeat(1000);
+        let mut paginator = TextPaginator::lazy(font, &content, 24.0, 10.0, 1.0, TextWrap::Char);
+        assert_eq!(paginator.page_count(), 0);
+
+        assert!(paginator.paginate_to(2));
+        assert_eq!(paginator.page_count(), 3);
+        assert!(!paginator.is_complete());
+
+        assert_eq!(paginator.page_of_offset(500), 500);
+        assert_eq!(paginator.page_count(), 501);
+        // pages already paginated are found without paginating further
+        assert_eq!(paginator.page_of_offset(3), 3);
+        assert_eq!(paginator.page_count(), 501);
+        assert!(!paginator.paginate_to(1000));
+        assert!(paginator.is_complete());
+    }
+
+    #[test]
+    fn test_orphan_control() {
+        // "c d e" is one paragraph, wrapped into a line per word
+        let content = "a\nb\nc d e";
+        assert_eq!(page_lines(&one_word_lines(content).orphans(1)), [3, 2]);
+        // "c" alone would be left at the bottom of the first page
+        assert_eq!(page_lines(&one_word_lines(content).orphans(2)), [2, 3]);
+    }
+
+    #[test]
+    fn test_widow_control() {
+        let content = "a\nb c d";
+        assert_eq!(page_lines(&one_word_lines(content).widows(1)), [3, 1]);
+        // "d" alone would start the second page
+        assert_eq!(page_lines(&one_word_lines(content).widows(2)), [2, 2]);
+    }
+
+    #[test]
+    fn test_widows_longer_than_a_page() {
+        // the paragraph can't leave 5 lines for the next page, so it breaks
+        // where it has to
+        let paginator = one_word_lines("a b c d").widows(5);
+        assert_eq!(page_lines(&paginator), [3, 1]);
+    }
 }
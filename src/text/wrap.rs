@@ -1,3 +1,5 @@
+use std::ops::Range;
+
 use ab_glyph::FontRef;
 
 use crate::style::TextWrap;
@@ -11,103 +13,197 @@ pub fn wrap_text(
     max_width: f32,
     wrap: TextWrap,
 ) -> Vec<String> {
-    match wrap {
-        TextWrap::None => vec![text.to_string()],
-        TextWrap::Word => wrap_words(font, text, font_size, max_width, false),
-        TextWrap::Char => wrap_chars(font, text, font_size, max_width),
-        TextWrap::WordChar => wrap_words(font, text, font_size, max_width, true),
+    let mut lines: Vec<String> = WrapLines::new(font, text, font_size, max_width, wrap, 0)
+        .map(|range| match wrap {
+            // word wrapping collapses runs of whitespace, newlines included,
+            // into single spaces
+            TextWrap::Word | TextWrap::WordChar => {
+                text[range].split_whitespace().collect::<Vec<_>>().join(" ")
+            }
+            TextWrap::None | TextWrap::Char => text[range].to_string(),
+        })
+        .collect();
+
+    if lines.is_empty() {
+        lines.push(String::new());
     }
+
+    lines
 }
 
-fn wrap_words(
-    font: &FontRef<'static>,
-    text: &str,
+/// Byte ranges in `text` of the lines `wrap_text` wraps it into, from
+/// `offset` on.
+///
+/// Each line only depends on where it starts, so the lines of a slice of
+/// `text` that starts and ends with a line are the same.
+#[derive(Debug, Clone)]
+pub(crate) struct WrapLines<'a> {
+    font: &'a FontRef<'static>,
+    text: &'a str,
     font_size: f32,
     max_width: f32,
-    break_long: bool,
-) -> Vec<String> {
-    let mut lines = Vec::new();
-    let mut current_line = String::new();
-    let mut current_width = 0.0f32;
-    let space_width = measure_char_width(font, ' ', font_size);
+    wrap: TextWrap,
+    offset: usize,
+}
 
-    for word in text.split_whitespace() {
-        let word_width = measure_text_width(font, word, font_size);
+impl<'a> WrapLines<'a> {
+    pub(crate) fn new(
+        font: &'a FontRef<'static>,
+        text: &'a str,
+        font_size: f32,
+        max_width: f32,
+        wrap: TextWrap,
+        offset: usize,
+    ) -> Self {
+        Self {
+            font,
+            text,
+            font_size,
+            max_width,
+            wrap,
+            offset,
+        }
+    }
 
-        let needed_width = if current_line.is_empty() {
-            word_width
-        } else {
-            space_width + word_width
+    fn next_words(&self, end: usize, break_long: bool) -> Range<usize> {
+        // text of only whitespace is an empty line
+        let Some(first) = self.next_word(self.offset, end) else {
+            return self.offset..self.offset;
         };
+        let first_width = measure_text_width(self.font, &self.text[first.clone()], self.font_size);
 
-        if current_width + needed_width <= max_width {
-            if !current_line.is_empty() {
-                current_line.push(' ');
-                current_width += space_width;
-            }
-            current_line.push_str(word);
-            current_width += word_width;
-        } else if word_width > max_width && break_long {
-            if !current_line.is_empty() {
-                lines.push(current_line);
-                current_line = String::new();
-                current_width = 0.0;
-            }
-            let char_lines = wrap_chars(font, word, font_size, max_width);
-            let char_lines_len = char_lines.len();
-            for (i, char_line) in char_lines.into_iter().enumerate() {
-                if i == char_lines_len - 1 {
-                    current_line = char_line;
-                    current_width = measure_text_width(font, &current_line, font_size);
-                } else {
-                    lines.push(char_line);
-                }
+        let (mut line, mut width) = if first_width > self.max_width && break_long {
+            let (end, width) = self.fit_chars(first.clone());
+            if end < first.end {
+                // the rest of the word continues on the next line
+                return first.start..end;
             }
+            (first, width)
         } else {
-            if !current_line.is_empty() {
-                lines.push(current_line);
+            (first, first_width)
+        };
+
+        let space_width = measure_char_width(self.font, ' ', self.font_size);
+        while let Some(word) = self.next_word(line.end, end) {
+            let word_width =
+                measure_text_width(self.font, &self.text[word.clone()], self.font_size);
+            if width + space_width + word_width > self.max_width {
+                break;
             }
-            current_line = word.to_string();
-            current_width = word_width;
+            width += space_width + word_width;
+            line.end = word.end;
         }
+
+        line
     }
 
-    if !current_line.is_empty() {
-        lines.push(current_line);
+    /// Next word in `from..end`
+    fn next_word(&self, from: usize, end: usize) -> Option<Range<usize>> {
+        let rest = &self.text[from..end];
+        let start = from + rest.find(|c: char| !c.is_whitespace())?;
+        let word_end = self.text[start..end]
+            .find(char::is_whitespace)
+            .map_or(end, |len| start + len);
+        Some(start..word_end)
     }
 
-    if lines.is_empty() {
-        lines.push(String::new());
+    fn next_chars(&self, end: usize) -> Range<usize> {
+        let (end, _) = self.fit_chars(self.offset..end);
+        self.offset..end
     }
 
-    lines
+    /// End of the chars of `range` that fit on one line, at least one, and
+    /// their width
+    fn fit_chars(&self, range: Range<usize>) -> (usize, f32) {
+        let mut end = range.start;
+        let mut width = 0.0f32;
+        for (i, ch) in self.text[range.clone()].char_indices() {
+            let char_width = measure_char_width(self.font, ch, self.font_size);
+            if width + char_width > self.max_width && end > range.start {
+                break;
+            }
+            width += char_width;
+            end = range.start + i + ch.len_utf8();
+        }
+        (end, width)
+    }
 }
 
-fn wrap_chars(font: &FontRef<'static>, text: &str, font_size: f32, max_width: f32) -> Vec<String> {
-    let mut lines = Vec::new();
-    let mut current_line = String::new();
-    let mut current_width = 0.0f32;
-
-    for ch in text.chars() {
-        let char_width = measure_char_width(font, ch, font_size);
+impl Iterator for WrapLines<'_> {
+    type Item = Range<usize>;
 
-        if current_width + char_width > max_width && !current_line.is_empty() {
-            lines.push(current_line);
-            current_line = String::new();
-            current_width = 0.0;
+    fn next(&mut self) -> Option<Range<usize>> {
+        // empty text still has one empty line
+        let done = self.offset > self.text.len()
+            || (self.offset == self.text.len() && !self.text.is_empty());
+        if done {
+            return None;
         }
 
-        current_line.push(ch);
-        current_width += char_width;
+        let end = self.text.len();
+        let line = match self.wrap {
+            TextWrap::None => self.offset..end,
+            TextWrap::Word => self.next_words(end, false),
+            TextWrap::Char => self.next_chars(end),
+            TextWrap::WordChar => self.next_words(end, true),
+        };
+
+        // word wrapping drops the whitespace at the end of the text
+        let rest_is_blank = matches!(self.wrap, TextWrap::Word | TextWrap::WordChar)
+            && self.text[line.end..].trim().is_empty();
+        self.offset = if line.end == end || rest_is_blank {
+            end + 1
+        } else {
+            line.end
+        };
+        Some(line)
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::font::Fonts;
+    use crate::style::TextFont;
 
-    if !current_line.is_empty() {
-        lines.push(current_line);
+    fn wrap(text: &str, wrap: TextWrap) -> Vec<String> {
+        let fonts = Fonts::new();
+        let font = fonts.get(TextFont::NotosansRegular);
+        wrap_text(font, text, 24.0, 400.0, wrap)
     }
 
-    if lines.is_empty() {
-        lines.push(String::new());
+    #[test]
+    fn test_newlines_are_whitespace() {
+        assert_eq!(wrap("one\ntwo  three", TextWrap::Word), ["one two three"]);
+        assert_eq!(wrap("one\n\ntwo", TextWrap::WordChar), ["one two"]);
+        assert_eq!(wrap("one\ntwo", TextWrap::Char), ["one\ntwo"]);
+        assert_eq!(wrap("one\ntwo", TextWrap::None), ["one\ntwo"]);
+        assert_eq!(wrap(" \n ", TextWrap::Word), [""]);
+        assert_eq!(wrap("", TextWrap::Char), [""]);
     }
 
-    lines
+    #[test]
+    fn test_word_and_char_wrapping() {
+        let fonts = Fonts::new();
+        let font = fonts.get(TextFont::NotosansRegular);
+        let width = measure_text_width(font, "abc", 24.0) + 1.0;
+
+        let lines = wrap_text(font, "abc abcdef ab", 24.0, width, TextWrap::Word);
+        assert_eq!(lines, ["abc", "abcdef", "ab"]);
+        let lines = wrap_text(font, "abc abcdef ab", 24.0, width, TextWrap::WordChar);
+        assert_eq!(lines, ["abc", "abc", "def", "ab"]);
+        let lines = wrap_text(font, "abcabcab", 24.0, width, TextWrap::Char);
+        assert_eq!(lines, ["abc", "abc", "ab"]);
+    }
+
+    #[test]
+    fn test_lines_match_wrap_text() {
+        let fonts = Fonts::new();
+        let font = fonts.get(TextFont::NotosansRegular);
+        let text = "alpha  beta\ngamma delta epsilon";
+        let lines: Vec<String> = WrapLines::new(font, text, 24.0, 120.0, TextWrap::Word, 0)
+            .map(|range| text[range].split_whitespace().collect::<Vec<_>>().join(" "))
+            .collect();
+        assert_eq!(lines, wrap_text(font, text, 24.0, 120.0, TextWrap::Word));
+    }
 }